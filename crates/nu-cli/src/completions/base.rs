@@ -43,14 +43,37 @@ pub trait Completer {
             SortBy::None => {}
         };
 
+        prefer_exact_case_matches(&mut filtered_items, &prefix);
+
         filtered_items
     }
 }
 
-#[derive(Debug, Default, PartialEq)]
+/// Stably moves suggestions that match `prefix` byte-for-byte ahead of ones
+/// that only matched case-insensitively, without disturbing the relative
+/// order within either group. A no-op when `prefix` is empty or every
+/// suggestion already matches its casing exactly (e.g. `case_sensitive`).
+pub(super) fn prefer_exact_case_matches(items: &mut [SemanticSuggestion], prefix: &[u8]) {
+    if prefix.is_empty() {
+        return;
+    }
+    items.sort_by_key(|item| !item.suggestion.value.as_bytes().starts_with(prefix));
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct SemanticSuggestion {
     pub suggestion: Suggestion,
     pub kind: Option<SuggestionKind>,
+    /// The fuzzy match score against the typed prefix, when
+    /// `completions.algorithm` is `"fuzzy"`. `None` for other algorithms, so
+    /// callers (e.g. an LSP wanting to merge and re-rank suggestions) can tell
+    /// a real low score apart from "not scored".
+    pub match_score: Option<f64>,
+    /// Set when this is the only suggestion in the returned set and it
+    /// exactly completes the current token, so a UI that auto-accepts
+    /// unambiguous completions doesn't have to re-derive that from the
+    /// suggestion count itself.
+    pub is_unambiguous_match: bool,
 }
 
 // TODO: think about name: maybe suggestion context?
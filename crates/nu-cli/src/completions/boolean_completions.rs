@@ -0,0 +1,55 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SuggestionKind};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span, Type,
+};
+use reedline::Suggestion;
+
+/// Completes `bool`-typed values (e.g. a `def`/`extern` flag or positional
+/// declared as `: bool`) to `true`/`false`, e.g. `spam --enabled=<tab>`.
+#[derive(Clone, Default)]
+pub struct BooleanCompletion {}
+
+impl BooleanCompletion {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Completer for BooleanCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        ["true", "false"]
+            .iter()
+            .filter(|candidate| {
+                options
+                    .match_algorithm
+                    .matches_u8(candidate.as_bytes(), &prefix)
+            })
+            .map(|candidate| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: candidate.to_string(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: Some(SuggestionKind::Type(Type::Bool)),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
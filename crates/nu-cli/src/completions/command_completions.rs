@@ -4,7 +4,8 @@ use crate::{
 };
 use nu_parser::FlatShape;
 use nu_protocol::{
-    engine::{CachedFile, Stack, StateWorkingSet},
+    ast::Expr,
+    engine::{CachedFile, Command, Stack, StateWorkingSet},
     Span,
 };
 use reedline::Suggestion;
@@ -88,9 +89,40 @@ impl CommandCompletion {
         find_externals: bool,
         match_algorithm: MatchAlgorithm,
     ) -> Vec<SemanticSuggestion> {
-        let partial = working_set.get_span_contents(span);
+        self.complete_commands_matching(
+            working_set,
+            span,
+            working_set.get_span_contents(span),
+            offset,
+            find_externals,
+            match_algorithm,
+        )
+    }
+
+    /// Like [`Self::complete_commands`], but matches against `partial` rather
+    /// than the literal text at `span`. This lets subcommand completion look
+    /// past an alias to the command it expands to, e.g. `alias f = foo` with
+    /// `f ba` matching `foo bar`, while still replacing the text the user
+    /// actually typed (`span`).
+    #[allow(clippy::too_many_arguments)]
+    fn complete_commands_matching(
+        &self,
+        working_set: &StateWorkingSet,
+        span: Span,
+        partial: &[u8],
+        offset: usize,
+        find_externals: bool,
+        match_algorithm: MatchAlgorithm,
+    ) -> Vec<SemanticSuggestion> {
+        let filter_predicate = |command_name: &[u8], command: &dyn Command| {
+            match_algorithm.matches_u8(command_name, partial)
+                || command
+                    .search_terms()
+                    .iter()
+                    .any(|term| match_algorithm.matches_u8(term.as_bytes(), partial))
+        };
 
-        let filter_predicate = |command: &[u8]| match_algorithm.matches_u8(command, partial);
+        let show_examples = working_set.get_config().completions_show_examples;
 
         let mut results = working_set
             .find_commands_by_predicate(filter_predicate, true)
@@ -98,17 +130,22 @@ impl CommandCompletion {
             .map(move |x| SemanticSuggestion {
                 suggestion: Suggestion {
                     value: String::from_utf8_lossy(&x.0).to_string(),
-                    description: x.1,
+                    description: if show_examples {
+                        with_example_hint(working_set, &x.0, x.1)
+                    } else {
+                        x.1
+                    },
                     style: None,
                     extra: None,
                     span: reedline::Span::new(span.start - offset, span.end - offset),
                     append_whitespace: true,
                 },
                 kind: Some(SuggestionKind::Command(x.2)),
+                match_score: None,
+                is_unambiguous_match: false,
             })
             .collect::<Vec<_>>();
 
-        let partial = working_set.get_span_contents(span);
         let partial = String::from_utf8_lossy(partial).to_string();
 
         if find_externals {
@@ -126,6 +163,8 @@ impl CommandCompletion {
                     },
                     // TODO: is there a way to create a test?
                     kind: None,
+                    match_score: None,
+                    is_unambiguous_match: false,
                 });
 
             let results_strings: Vec<String> =
@@ -143,6 +182,8 @@ impl CommandCompletion {
                             append_whitespace: true,
                         },
                         kind: external.kind,
+                        match_score: None,
+                        is_unambiguous_match: false,
                     })
                 } else {
                     results.push(external)
@@ -186,13 +227,34 @@ impl Completer for CommandCompletion {
 
         // The last item here would be the earliest shape that could possible by part of this subcommand
         let subcommands = if let Some(last) = last {
-            self.complete_commands(
+            let subcommand_span = Span::new(last.0.start, pos);
+            let mut subcommands = self.complete_commands(
                 working_set,
-                Span::new(last.0.start, pos),
+                subcommand_span,
                 offset,
                 false,
                 options.match_algorithm,
-            )
+            );
+
+            // If nothing matched, the leading word might be an alias (e.g.
+            // `alias f = foo` with `foo bar` defined) rather than the real
+            // command name subcommands are indexed under. Retry the search
+            // with the alias resolved.
+            if subcommands.is_empty() {
+                let typed = working_set.get_span_contents(subcommand_span);
+                if let Some(resolved) = resolve_alias_head(working_set, typed) {
+                    subcommands = self.complete_commands_matching(
+                        working_set,
+                        subcommand_span,
+                        &resolved,
+                        offset,
+                        false,
+                        options.match_algorithm,
+                    );
+                }
+            }
+
+            subcommands
         } else {
             vec![]
         };
@@ -231,6 +293,71 @@ impl Completer for CommandCompletion {
     }
 }
 
+/// Appends a command's first `examples()` entry to `description`, per
+/// `completions.show_examples`, as a hint at the argument pattern to type
+/// next. Falls back to `description` unchanged if the command has no
+/// examples.
+fn with_example_hint(
+    working_set: &StateWorkingSet,
+    command_name: &[u8],
+    description: Option<String>,
+) -> Option<String> {
+    let example = working_set
+        .find_decl(command_name)
+        .and_then(|decl_id| working_set.get_decl(decl_id).examples().into_iter().next());
+
+    match example {
+        Some(example) => Some(match description {
+            Some(description) if !description.is_empty() => {
+                format!("{description}\n\n{}", example.example)
+            }
+            _ => example.example.to_string(),
+        }),
+        None => description,
+    }
+}
+
+/// Repeatedly replaces the leading word of `text` with what it's aliased to,
+/// as long as it resolves to an alias, e.g. `foo bar` for `f bar` given
+/// `alias f = foo`. Returns `None` if the leading word isn't an alias.
+fn resolve_alias_head(working_set: &StateWorkingSet, text: &[u8]) -> Option<Vec<u8>> {
+    let split_at = text
+        .iter()
+        .position(|b| b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    let (head, rest) = text.split_at(split_at);
+    let mut head = head.to_vec();
+    let mut resolved = false;
+
+    // Bounded to guard against alias cycles; real alias chains are shallow.
+    for _ in 0..8 {
+        let Some(decl_id) = working_set.find_decl(&head) else {
+            break;
+        };
+        let Some(alias) = working_set.get_decl(decl_id).as_alias() else {
+            break;
+        };
+        let new_head = match &alias.wrapped_call.expr {
+            Expr::Call(call) => working_set
+                .get_decl(call.decl_id)
+                .name()
+                .as_bytes()
+                .to_vec(),
+            Expr::ExternalCall(head_expr, _) => {
+                working_set.get_span_contents(head_expr.span).to_vec()
+            }
+            _ => break,
+        };
+        if new_head == head {
+            break;
+        }
+        head = new_head;
+        resolved = true;
+    }
+
+    resolved.then(|| [head.as_slice(), rest].concat())
+}
+
 pub fn find_non_whitespace_index(contents: &[u8], start: usize) -> usize {
     match contents.get(start..) {
         Some(contents) => {
@@ -1,38 +1,289 @@
 use crate::completions::{
-    CommandCompletion, Completer, CompletionOptions, CustomCompletion, DirectoryCompletion,
-    DotNuCompletion, FileCompletion, FlagCompletion, VariableCompletion,
+    BooleanCompletion, CommandCompletion, Completer, CompletionOptions, ConfigValueCompletion,
+    CustomCompletion, DirectoryCompletion, DotNuCompletion, FileCompletion, FlagCompletion,
+    HelpCompletion, LiteralCellPathCompletion, MatchAlgorithm, ModuleMemberCompletion,
+    OperatorCompletion, OverlayCompletion, PathFilter, PipelineOutputCompletion,
+    RecentDirectoryCompletion, RecordKeyCompletion, UnitSuffixCompletion, VariableCompletion,
 };
 use nu_color_config::{color_record_to_nustyle, lookup_ansi_color_style};
 use nu_engine::eval_block;
 use nu_parser::{flatten_pipeline_element, parse, FlatShape};
 use nu_protocol::{
+    ast::{Argument, Block, Expr, Expression, PipelineElement, RecordItem},
     debugger::WithoutDebug,
     engine::{Closure, EngineState, Stack, StateWorkingSet},
-    PipelineData, Span, Value,
+    BlockId, CompletionSort, PipelineData, ShellError, Signature, Span, SyntaxShape, Type, VarId,
+    Value,
 };
+use nu_utils::IgnoreCaseExt;
 use reedline::{Completer as ReedlineCompleter, Suggestion};
-use std::{str, sync::Arc};
+use std::{
+    ops::ControlFlow,
+    path::is_separator,
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use super::base::{SemanticSuggestion, SuggestionKind};
 
-#[derive(Clone)]
+/// Completion categories that can be turned off when constructing a
+/// [`NuCompleter`], e.g. for a sandboxed embedder that only wants
+/// command/flag completions and no filesystem or external-completer access.
+///
+/// The default (all `false`) disables nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompleterKinds {
+    pub file: bool,
+    pub directory: bool,
+    pub external: bool,
+}
+
+/// Result of running an external completer closure.
+struct ExternalCompletionOutput {
+    suggestions: Vec<SemanticSuggestion>,
+    /// Set when the closure returned `{ completions: [...], fallback: true }`
+    /// instead of a bare list, asking for file completions to be appended.
+    fallback: bool,
+}
+
+/// A Rust-side completer for a single command's arguments, registered via
+/// [`NuCompleter::register_command_completer`]. Receives the command line
+/// split into spans (`spans[0]` is the command name itself) and returns the
+/// suggestions for the span under the cursor.
+pub type CommandCompleterFn = Arc<dyn Fn(&[String]) -> Vec<Suggestion> + Send + Sync>;
+
+/// Per-call latency breakdown for a single [`NuCompleter::complete`], handed
+/// to the sink registered via [`NuCompleter::set_metrics_sink`]. Useful for
+/// diagnosing slow completions, e.g. file completions on a network
+/// filesystem. A zero field means that phase didn't run for this call, not
+/// that it ran instantly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompletionMetrics {
+    pub parse_time: Duration,
+    pub file_scan_time: Duration,
+    pub custom_completer_time: Duration,
+}
+
+/// Shared state between [`NuCompleter::complete_async`] and the worker
+/// thread computing its result.
+#[cfg(feature = "async")]
+struct CompleteFutureState {
+    result: Option<Vec<Suggestion>>,
+    waker: Option<std::task::Waker>,
+}
+
+/// The [`Future`](std::future::Future) returned by
+/// [`NuCompleter::complete_async`], resolving to the same suggestions
+/// [`NuCompleter::complete`] would, once the background thread finishes.
+#[cfg(feature = "async")]
+pub struct CompleteFuture {
+    shared: Arc<std::sync::Mutex<CompleteFutureState>>,
+}
+
+#[cfg(feature = "async")]
+impl std::future::Future for CompleteFuture {
+    type Output = Vec<Suggestion>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut state = self.shared.lock().expect("completion worker poisoned");
+        match state.result.take() {
+            Some(result) => std::task::Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct NuCompleter {
     engine_state: Arc<EngineState>,
     stack: Stack,
+    disabled: CompleterKinds,
+    command_completers: std::collections::HashMap<String, CommandCompleterFn>,
+    /// Maps a command name to a base directory its file/path completions
+    /// should be resolved relative to instead of the current working
+    /// directory. Checked before `completions.path_roots` in `$env.config`,
+    /// so an embedder's programmatic mapping can override the user's.
+    path_roots: std::collections::HashMap<String, String>,
+    /// Consulted by file completions to drop entries outside an allowed
+    /// root, e.g. for a sandboxed embedder. See
+    /// [`NuCompleter::set_path_filter`].
+    path_filter: Option<PathFilter>,
+    metrics_sink: Option<Arc<dyn Fn(CompletionMetrics) + Send + Sync>>,
+    /// How many times each suggestion value has been run, used to boost
+    /// frequently-used commands when `completions.sort` is `"smart"`. Callers
+    /// (e.g. the REPL, after a line is accepted) report usage through
+    /// [`NuCompleter::record_command_usage`]; nothing populates this
+    /// automatically.
+    command_usage: std::collections::HashMap<String, u32>,
+    /// Fast-path cache for `completion_helper`: the previous call's fully
+    /// resolved candidate list, reused when the user has only appended more
+    /// characters to the same token since then. See
+    /// [`NuCompleter::completion_fast_path`].
+    incremental_cache: Option<CachedCompletion>,
+}
+
+/// A previous [`NuCompleter::completion_helper`] result, kept around so a
+/// query that only grows the same token (rather than editing earlier text or
+/// switching tokens) can be served by filtering these candidates instead of
+/// re-parsing and re-fetching from scratch.
+#[derive(Clone)]
+struct CachedCompletion {
+    /// The line up to the cursor when `candidates` were fetched.
+    line_prefix: String,
+    candidates: Vec<SemanticSuggestion>,
 }
 
 impl NuCompleter {
     pub fn new(engine_state: Arc<EngineState>, stack: Arc<Stack>) -> Self {
+        Self::with_disabled(engine_state, stack, CompleterKinds::default())
+    }
+
+    /// Constructs a completer with the given completion categories disabled.
+    pub fn with_disabled(
+        engine_state: Arc<EngineState>,
+        stack: Arc<Stack>,
+        disabled: CompleterKinds,
+    ) -> Self {
         Self {
             engine_state,
             stack: Stack::with_parent(stack).reset_out_dest().capture(),
+            disabled,
+            command_completers: std::collections::HashMap::new(),
+            path_roots: std::collections::HashMap::new(),
+            path_filter: None,
+            metrics_sink: None,
+            command_usage: std::collections::HashMap::new(),
+            incremental_cache: None,
+        }
+    }
+
+    /// Records that `name` (typically a command's first span) was just run,
+    /// so `completions.sort = "smart"` can boost it above less-frequently-used
+    /// suggestions the next time it's a completion candidate.
+    pub fn record_command_usage(&mut self, name: impl Into<String>) {
+        *self.command_usage.entry(name.into()).or_insert(0) += 1;
+    }
+
+    /// Registers a sink that receives a [`CompletionMetrics`] breakdown after
+    /// each completion, for embedders diagnosing slow completions (e.g. on a
+    /// network filesystem). Pass `None` to stop collecting metrics.
+    pub fn set_metrics_sink(&mut self, sink: Option<Arc<dyn Fn(CompletionMetrics) + Send + Sync>>) {
+        self.metrics_sink = sink;
+    }
+
+    /// Registers a predicate consulted by file completions: an entry the
+    /// predicate rejects is dropped from the results, and if it's a
+    /// directory, traversal doesn't descend into it either. Useful for a
+    /// sandboxed embedder that needs to prevent completing outside an
+    /// allowed root. Pass `None` to stop filtering.
+    pub fn set_path_filter(&mut self, filter: Option<PathFilter>) {
+        self.path_filter = filter;
+    }
+
+    /// Registers a Rust-side completer for `name`'s arguments, consulted
+    /// before the built-in positional completion logic (cell paths, files,
+    /// flags, ...). Useful for embedders with dynamic values a nushell
+    /// custom completer can't reach, e.g. rows from an open database
+    /// connection. Registering the same name again replaces the previous
+    /// completer.
+    pub fn register_command_completer(&mut self, name: impl Into<String>, f: CommandCompleterFn) {
+        self.command_completers.insert(name.into(), f);
+    }
+
+    /// Maps `name` to `base_dir`, so `name`'s file/path completions resolve
+    /// relative to `base_dir` instead of the current working directory, e.g.
+    /// a command that always operates on a project data directory. `$env.
+    /// config.completions.path_roots` offers the same mapping to users;
+    /// registering the same name again replaces the previous mapping.
+    pub fn register_path_root(&mut self, name: impl Into<String>, base_dir: impl Into<String>) {
+        self.path_roots.insert(name.into(), base_dir.into());
+    }
+
+    /// Looks up the base directory `command_name`'s file completions should
+    /// be resolved relative to, if one was mapped via
+    /// [`NuCompleter::register_path_root`] or `completions.path_roots`.
+    fn path_root_for(&self, command_name: &str) -> Option<String> {
+        self.path_roots.get(command_name).cloned().or_else(|| {
+            self.engine_state
+                .get_config()
+                .completions_path_roots
+                .get(command_name)
+                .cloned()
+        })
+    }
+
+    /// Constructs a [`FileCompletion`], using `command_name`'s mapped base
+    /// directory (see [`NuCompleter::path_root_for`]) if one exists.
+    fn file_completer_for(&self, command_name: &str) -> FileCompletion {
+        match self.path_root_for(command_name) {
+            Some(base_dir) => FileCompletion::with_base_dir(base_dir),
+            None => FileCompletion::new(),
         }
+        .with_path_filter(self.path_filter.clone())
     }
 
     pub fn fetch_completions_at(&mut self, line: &str, pos: usize) -> Vec<SemanticSuggestion> {
         self.completion_helper(line, pos)
     }
 
+    /// Like [`ReedlineCompleter::complete`], but runs on a background thread
+    /// and returns a [`Future`](std::future::Future) instead of blocking the
+    /// caller, so a GUI embedder driving an async runtime doesn't stall its
+    /// UI thread on a slow external completer. `engine_state`/`stack` are
+    /// already `Arc`-shared (see [`NuCompleter::new`]), so cloning `self` to
+    /// move it onto the worker thread is cheap.
+    #[cfg(feature = "async")]
+    pub fn complete_async(&self, line: &str, pos: usize) -> CompleteFuture {
+        let shared = Arc::new(std::sync::Mutex::new(CompleteFutureState {
+            result: None,
+            waker: None,
+        }));
+        let worker_shared = Arc::clone(&shared);
+        let mut completer = self.clone();
+        let line = line.to_string();
+        std::thread::spawn(move || {
+            let result = completer.complete(&line, pos);
+            let mut state = worker_shared.lock().expect("completion worker poisoned");
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        CompleteFuture { shared }
+    }
+
+    /// Like [`ReedlineCompleter::complete`], but streams suggestions through
+    /// `f` one at a time instead of collecting them into a `Vec` the caller
+    /// may only partially consume. Returning [`ControlFlow::Break`] from `f`
+    /// stops iteration early, e.g. once a TUI has enough rows to render.
+    ///
+    /// Completers sort their results before returning them, so this doesn't
+    /// avoid computing the full set of completions -- only the `Vec`
+    /// allocation and any work `f` would have done on suggestions past the
+    /// point it decided to stop.
+    pub fn complete_streaming(
+        &mut self,
+        line: &str,
+        pos: usize,
+        mut f: impl FnMut(Suggestion) -> ControlFlow<()>,
+    ) {
+        for suggestion in self.completion_helper(line, pos) {
+            if f(suggestion.suggestion).is_break() {
+                break;
+            }
+        }
+    }
+
     // Process the completion for a given completer
     fn process_completion<T: Completer>(
         &self,
@@ -47,7 +298,16 @@ impl NuCompleter {
 
         let options = CompletionOptions {
             case_sensitive: config.case_sensitive_completions,
-            match_algorithm: config.completion_algorithm.into(),
+            match_algorithm: MatchAlgorithm::from_config(
+                config.completion_algorithm,
+                config.completions_fuzzy_anchor,
+            ),
+            hidden_files: config.completions_hidden_files,
+            append_slash: config.completions_append_slash,
+            use_ignore_files: config.completions_use_ignore_files,
+            dirs_first: config.completions_dirs_first,
+            sort: config.completion_sort,
+            partial: config.partial_completions,
             ..Default::default()
         };
 
@@ -63,18 +323,133 @@ impl NuCompleter {
         );
 
         // Sort
-        suggestions = completer.sort(suggestions, prefix);
+        suggestions = completer.sort(suggestions, prefix.clone());
+        if options.sort == CompletionSort::Smart {
+            suggestions = self.sort_smart(suggestions, &prefix, &options);
+        }
+
+        // Expose the fuzzy match score so external consumers (e.g. an LSP) can
+        // re-rank suggestions themselves instead of trusting our sort order.
+        let prefix_str = String::from_utf8_lossy(&prefix).to_string();
+        for suggestion in &mut suggestions {
+            suggestion.match_score = options
+                .match_algorithm
+                .fuzzy_score(&suggestion.suggestion.value, &prefix_str);
+        }
+
+        // Flag the single suggestion left after fetch/sort as unambiguous,
+        // so a caller that wants to auto-accept a sole exact match doesn't
+        // have to re-derive "there's only one, and it's not a fuzzy fallback".
+        if let [suggestion] = suggestions.as_mut_slice() {
+            let matches = if options.case_sensitive {
+                suggestion.suggestion.value.starts_with(&prefix_str)
+            } else {
+                suggestion
+                    .suggestion
+                    .value
+                    .to_folded_case()
+                    .starts_with(&prefix_str.to_folded_case())
+            };
+            suggestion.is_unambiguous_match = matches;
+        }
 
         suggestions
     }
 
-    fn external_completion(
+    /// Reorders suggestions for `completions.sort = "smart"`: suggestions
+    /// whose value starts with `prefix` come before ones that only matched
+    /// through fuzzy/substring matching; within each of those two groups,
+    /// ties are broken by descending usage count reported through
+    /// [`NuCompleter::record_command_usage`], and finally alphabetically.
+    fn sort_smart(
+        &self,
+        mut suggestions: Vec<SemanticSuggestion>,
+        prefix: &[u8],
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let prefix_str = String::from_utf8_lossy(prefix).to_string();
+        let starts_with = |value: &str| -> bool {
+            if options.case_sensitive {
+                value.starts_with(&prefix_str)
+            } else {
+                value
+                    .to_folded_case()
+                    .starts_with(&prefix_str.to_folded_case())
+            }
+        };
+
+        suggestions.sort_by(|a, b| {
+            starts_with(&b.suggestion.value)
+                .cmp(&starts_with(&a.suggestion.value))
+                .then_with(|| {
+                    let a_uses = self
+                        .command_usage
+                        .get(&a.suggestion.value)
+                        .copied()
+                        .unwrap_or(0);
+                    let b_uses = self
+                        .command_usage
+                        .get(&b.suggestion.value)
+                        .copied()
+                        .unwrap_or(0);
+                    b_uses.cmp(&a_uses)
+                })
+                .then_with(|| a.suggestion.value.cmp(&b.suggestion.value))
+        });
+
+        suggestions
+    }
+
+    /// Runs the external completer and, if it asked for a file-completion
+    /// fallback, appends file completions after its own (deduplicated by
+    /// suggestion value, external results winning ties).
+    fn external_completion_with_fallback(
         &self,
         closure: &Closure,
         spans: &[String],
+        working_set: &StateWorkingSet,
+        prefix: Vec<u8>,
         offset: usize,
         span: Span,
+        pos: usize,
     ) -> Option<Vec<SemanticSuggestion>> {
+        let ExternalCompletionOutput {
+            suggestions,
+            fallback,
+        } = self.external_completion(closure, spans, offset, span, pos)?;
+
+        if !fallback || self.disabled.file {
+            return Some(suggestions);
+        }
+
+        let mut seen: std::collections::HashSet<String> = suggestions
+            .iter()
+            .map(|s| s.suggestion.value.clone())
+            .collect();
+        let mut merged = suggestions;
+        let mut file_completer = spans
+            .first()
+            .map(|name| self.file_completer_for(name))
+            .unwrap_or_default();
+        let file_suggestions =
+            self.process_completion(&mut file_completer, working_set, prefix, span, offset, pos);
+        merged.extend(
+            file_suggestions
+                .into_iter()
+                .filter(|s| seen.insert(s.suggestion.value.clone())),
+        );
+
+        Some(merged)
+    }
+
+    fn external_completion(
+        &self,
+        closure: &Closure,
+        spans: &[String],
+        offset: usize,
+        span: Span,
+        pos: usize,
+    ) -> Option<ExternalCompletionOutput> {
         let block = self.engine_state.get_block(closure.block_id);
         let mut callee_stack = self
             .stack
@@ -96,29 +471,276 @@ impl NuCompleter {
             }
         }
 
-        let result = eval_block::<WithoutDebug>(
-            &self.engine_state,
-            &mut callee_stack,
-            block,
-            PipelineData::empty(),
-        );
+        // Cursor byte-offset within the current (last) token, so an external
+        // completer can handle mid-token completion (e.g. `gh api --he|ader`).
+        // Kept as an additional positional so existing single-argument
+        // completers keep working unchanged.
+        if let Some(pos_arg) = block.signature.required_positional.get(1) {
+            if let Some(var_id) = pos_arg.var_id {
+                let cursor_pos = pos.saturating_sub(span.start);
+                callee_stack.add_var(var_id, Value::int(cursor_pos as i64, Span::unknown()));
+            }
+        }
 
-        match result.and_then(|data| data.into_value(span)) {
-            Ok(value) => {
-                if let Value::List { vals, .. } = value {
-                    let result =
-                        map_value_completions(vals.iter(), Span::new(span.start, span.end), offset);
+        let timeout = self.engine_state.get_config().completions_external_timeout;
+        let result = if timeout > 0 {
+            self.eval_external_completer_with_timeout(
+                closure.block_id,
+                callee_stack,
+                Duration::from_nanos(timeout as u64),
+            )?
+        } else {
+            eval_block::<WithoutDebug>(
+                &self.engine_state,
+                &mut callee_stack,
+                block,
+                PipelineData::empty(),
+            )
+        };
 
-                    return Some(result);
-                }
+        match result.and_then(|data| data.into_value(span)) {
+            Ok(Value::List { vals, .. }) => {
+                return Some(ExternalCompletionOutput {
+                    suggestions: map_value_completions(
+                        vals.iter(),
+                        Span::new(span.start, span.end),
+                        offset,
+                    ),
+                    fallback: false,
+                });
+            }
+            // A completer can return `{ completions: [...], fallback: true }`
+            // instead of a bare list to supply some suggestions of its own
+            // while still asking for file completions to be appended.
+            Ok(Value::Record { val, .. }) => {
+                let suggestions = match val.get("completions") {
+                    Some(Value::List { vals, .. }) => {
+                        map_value_completions(vals.iter(), Span::new(span.start, span.end), offset)
+                    }
+                    _ => vec![],
+                };
+                let fallback = matches!(val.get("fallback"), Some(Value::Bool { val: true, .. }));
+
+                return Some(ExternalCompletionOutput {
+                    suggestions,
+                    fallback,
+                });
             }
+            Ok(_) => {}
             Err(err) => println!("failed to eval completer block: {err}"),
         }
 
         None
     }
 
+    /// Runs `block_id` on its own thread with a private `ctrlc` flag, and
+    /// gives up after `timeout`. If the closure is still running when the
+    /// timeout elapses, its `ctrlc` flag is set (so a `ctrlc`-aware command
+    /// like `sleep` unwinds on its own) and this returns `None`; the thread
+    /// is otherwise left to finish exiting rather than forcibly killed.
+    fn eval_external_completer_with_timeout(
+        &self,
+        block_id: BlockId,
+        mut callee_stack: Stack,
+        timeout: Duration,
+    ) -> Option<Result<PipelineData, ShellError>> {
+        let mut engine_state = (*self.engine_state).clone();
+        engine_state.ctrlc = Some(Arc::new(AtomicBool::new(false)));
+        let ctrlc = engine_state.ctrlc.clone();
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let block = engine_state.get_block(block_id);
+            let result = eval_block::<WithoutDebug>(
+                &engine_state,
+                &mut callee_stack,
+                block,
+                PipelineData::empty(),
+            );
+            let _ = tx.send(result);
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => Some(result),
+            Err(mpsc::RecvTimeoutError::Timeout | mpsc::RecvTimeoutError::Disconnected) => {
+                if let Some(ctrlc) = ctrlc {
+                    ctrlc.store(true, Ordering::Relaxed);
+                }
+                None
+            }
+        }
+    }
+
     fn completion_helper(&mut self, line: &str, pos: usize) -> Vec<SemanticSuggestion> {
+        if let Some(result) = self.completion_fast_path(line, pos) {
+            // No phase ran, so every field stays zero.
+            if let Some(sink) = &self.metrics_sink {
+                sink(CompletionMetrics::default());
+            }
+            self.incremental_cache = Some(CachedCompletion {
+                line_prefix: Self::line_up_to_cursor(line, pos).to_string(),
+                candidates: result.clone(),
+            });
+            return self.apply_transform(result);
+        }
+
+        let mut metrics = CompletionMetrics::default();
+        let result = self.completion_helper_timed(line, pos, &mut metrics);
+        if let Some(sink) = &self.metrics_sink {
+            sink(metrics);
+        }
+        self.incremental_cache = Some(CachedCompletion {
+            line_prefix: Self::line_up_to_cursor(line, pos).to_string(),
+            candidates: result.clone(),
+        });
+        self.apply_transform(result)
+    }
+
+    fn line_up_to_cursor(line: &str, pos: usize) -> &str {
+        if line.len() > pos {
+            &line[..pos]
+        } else {
+            line
+        }
+    }
+
+    /// Fast path for [`NuCompleter::completion_helper`]: when
+    /// `completions.algorithm` is `prefix` (the only algorithm where growing
+    /// the query can only ever shrink the match set, never reorder it) and
+    /// the user has just appended more characters to the same token, the
+    /// previous call's candidates are re-filtered by the grown prefix
+    /// instead of re-parsing and re-fetching from scratch. Returns `None` on
+    /// a cache miss (first call, a non-append edit, or a non-prefix
+    /// algorithm), which falls back to the normal path.
+    fn completion_fast_path(&self, line: &str, pos: usize) -> Option<Vec<SemanticSuggestion>> {
+        let cached = self.incremental_cache.as_ref()?;
+        let trimmed = Self::line_up_to_cursor(line, pos);
+
+        if trimmed.len() <= cached.line_prefix.len() || !trimmed.starts_with(&cached.line_prefix) {
+            return None;
+        }
+
+        // A registered command completer is an arbitrary Rust closure that
+        // isn't guaranteed to return a shrinking subset as the query grows,
+        // so always re-run it rather than trust the cache.
+        if let Some(command_name) = trimmed.split_whitespace().next() {
+            if self.command_completers.contains_key(command_name) {
+                return None;
+            }
+        }
+
+        // A token-ending character means completion now targets a different
+        // span than the one cached, so the cache no longer applies.
+        let appended = &trimmed[cached.line_prefix.len()..];
+        if appended
+            .chars()
+            .any(|c| c.is_whitespace() || "|;()[]{}\"'$.".contains(c))
+        {
+            return None;
+        }
+
+        let config = self.engine_state.get_config();
+        let algorithm = MatchAlgorithm::from_config(
+            config.completion_algorithm,
+            config.completions_fuzzy_anchor,
+        );
+        if !matches!(algorithm, MatchAlgorithm::Prefix) {
+            return None;
+        }
+
+        let case_sensitive = config.case_sensitive_completions;
+        let token_start = cached.candidates.first()?.suggestion.span.start;
+        let new_typed = trimmed.get(token_start..)?;
+
+        // Flag completion can grow the candidate *set* as more characters
+        // are typed (e.g. short-flag cluster continuation: `-a` -> `-al`
+        // suggests appending yet another switch), so filtering the old set
+        // isn't equivalent to re-fetching. Let those fall through to the
+        // normal path.
+        if new_typed.starts_with('-') {
+            return None;
+        }
+
+        let matches = |value: &str| -> bool {
+            if case_sensitive {
+                value.starts_with(new_typed)
+            } else {
+                value
+                    .to_folded_case()
+                    .starts_with(&new_typed.to_folded_case())
+            }
+        };
+
+        let mut candidates: Vec<SemanticSuggestion> = cached
+            .candidates
+            .iter()
+            .filter(|s| matches(&s.suggestion.value))
+            .cloned()
+            .collect();
+
+        for candidate in &mut candidates {
+            candidate.suggestion.span.end = trimmed.len();
+        }
+
+        if let [suggestion] = candidates.as_mut_slice() {
+            suggestion.is_unambiguous_match = matches(&suggestion.suggestion.value);
+        } else {
+            for candidate in &mut candidates {
+                candidate.is_unambiguous_match = false;
+            }
+        }
+
+        Some(candidates)
+    }
+
+    /// Runs `completions.transform`, if set, over each suggestion's value.
+    /// A transform that doesn't return a string leaves that suggestion
+    /// unchanged rather than erroring, since a typo in the transform
+    /// shouldn't make completion unusable.
+    fn apply_transform(&self, mut suggestions: Vec<SemanticSuggestion>) -> Vec<SemanticSuggestion> {
+        let Some(closure) = self.engine_state.get_config().completions_transform.clone() else {
+            return suggestions;
+        };
+
+        let block = self.engine_state.get_block(closure.block_id);
+        for suggestion in &mut suggestions {
+            let mut callee_stack = self
+                .stack
+                .captures_to_stack_preserve_out_dest(closure.captures.clone());
+            if let Some(var_id) = block
+                .signature
+                .required_positional
+                .first()
+                .and_then(|arg| arg.var_id)
+            {
+                callee_stack.add_var(
+                    var_id,
+                    Value::string(suggestion.suggestion.value.clone(), Span::unknown()),
+                );
+            }
+
+            let result = eval_block::<WithoutDebug>(
+                &self.engine_state,
+                &mut callee_stack,
+                block,
+                PipelineData::empty(),
+            )
+            .and_then(|data| data.into_value(Span::unknown()));
+
+            if let Ok(Value::String { val, .. }) = result {
+                suggestion.suggestion.value = val;
+            }
+        }
+
+        suggestions
+    }
+
+    fn completion_helper_timed(
+        &mut self,
+        line: &str,
+        pos: usize,
+        metrics: &mut CompletionMetrics,
+    ) -> Vec<SemanticSuggestion> {
         let mut working_set = StateWorkingSet::new(&self.engine_state);
         let offset = working_set.next_span_start();
         // TODO: Callers should be trimming the line themselves
@@ -127,16 +749,59 @@ impl NuCompleter {
         // place even with `only_buffer_difference: true`
         let fake_offset = offset + line.len() - pos;
         let pos = offset + line.len();
-        let initial_line = line.to_string();
         let mut line = line.to_string();
         line.push('a');
 
-        let config = self.engine_state.get_config();
+        let parse_start = Instant::now();
+        let block = parse(&mut working_set, Some("completer"), line.as_bytes(), false);
+        metrics.parse_time = parse_start.elapsed();
 
-        let output = parse(&mut working_set, Some("completer"), line.as_bytes(), false);
+        self.complete_in_block_helper(&working_set, &block, fake_offset, pos, metrics)
+    }
+
+    /// Like [`NuCompleter::complete`], but takes a [`Block`] and
+    /// [`StateWorkingSet`] the caller already parsed (e.g. an LSP server that
+    /// keeps the open document parsed) instead of parsing `line` again.
+    /// `pos` is an absolute position in `working_set`'s span space, i.e. the
+    /// same space `block`'s spans live in -- not a byte offset into some
+    /// separate `line` string. Suggestion spans are returned in that same
+    /// absolute space, unlike [`NuCompleter::complete`] which returns spans
+    /// relative to its `line` argument.
+    pub fn complete_in_block(
+        &mut self,
+        working_set: &StateWorkingSet,
+        block: &Block,
+        pos: usize,
+    ) -> Vec<SemanticSuggestion> {
+        let mut metrics = CompletionMetrics::default();
+        let result = self.complete_in_block_helper(working_set, block, 0, pos, &mut metrics);
+        self.apply_transform(result)
+    }
+
+    fn complete_in_block_helper(
+        &self,
+        working_set: &StateWorkingSet,
+        block: &Block,
+        span_offset: usize,
+        pos: usize,
+        metrics: &mut CompletionMetrics,
+    ) -> Vec<SemanticSuggestion> {
+        // The text of the current line up to the cursor, used as the
+        // `context` argument passed to `@completer` closures. Found by
+        // scanning back from `pos` for the closest preceding newline (or the
+        // very start of the working set's source, for a single-line `line`).
+        let line_start = working_set
+            .get_span_contents(Span::new(0, pos))
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |idx| idx + 1);
+        let initial_line =
+            String::from_utf8_lossy(working_set.get_span_contents(Span::new(line_start, pos)))
+                .to_string();
+        let config = self.engine_state.get_config();
 
-        for pipeline in &output.pipelines {
-            for pipeline_element in &pipeline.elements {
+        for pipeline in &block.pipelines {
+            for (element_idx, pipeline_element) in pipeline.elements.iter().enumerate() {
                 let flattened = flatten_pipeline_element(&working_set, pipeline_element);
                 let mut spans: Vec<String> = vec![];
 
@@ -168,56 +833,203 @@ impl NuCompleter {
                     // Complete based on the last span
                     if is_last_span {
                         // Context variables
-                        let most_left_var =
+                        let mut most_left_var =
                             most_left_variable(flat_idx, &working_set, flattened.clone());
 
                         // Create a new span
-                        let new_span = Span::new(flat.0.start, flat.0.end - 1);
+                        let mut new_span = Span::new(flat.0.start, flat.0.end - 1);
 
                         // Parses the prefix. Completion should look up to the cursor position, not after.
                         let mut prefix = working_set.get_span_contents(flat.0).to_vec();
                         let index = pos - flat.0.start;
                         prefix.drain(index..);
 
+                        // A `...$rec` argument that the called command's signature has no
+                        // `rest_positional` for fails to parse and collapses to a single
+                        // `Expr::Garbage` spanning the whole "...$rec.a.b" text (see
+                        // `ParseError::UnexpectedSpreadArg`), so `most_left_var` above never
+                        // sees the `Variable`/`String` flat shapes it normally walks. Recover
+                        // the same (head, sublevels) context straight from the token text so
+                        // cell path completion still works while the command is mid-typed.
+                        if most_left_var.is_none() {
+                            if let Some((stripped_len, var_context)) =
+                                spread_variable_context(&prefix)
+                            {
+                                prefix.drain(..stripped_len);
+                                new_span = Span::new(new_span.start + stripped_len, new_span.end);
+                                most_left_var = Some(var_context);
+                            }
+                        }
+
+                        // A registered command completer takes priority over
+                        // the built-in positional logic below.
+                        if flat_idx > 0 {
+                            if let Some(command_completer) = spans
+                                .first()
+                                .and_then(|name| self.command_completers.get(name.as_str()))
+                            {
+                                return command_completer(&spans)
+                                    .into_iter()
+                                    .map(|mut suggestion| {
+                                        suggestion.span = reedline::Span {
+                                            start: new_span.start - span_offset,
+                                            end: new_span.end - span_offset,
+                                        };
+                                        SemanticSuggestion::from(suggestion)
+                                    })
+                                    .collect();
+                            }
+                        }
+
                         // Variables completion
                         if prefix.starts_with(b"$") || most_left_var.is_some() {
-                            let mut completer =
-                                VariableCompletion::new(most_left_var.unwrap_or((vec![], vec![])));
+                            let closure_params = find_enclosing_closure_params(
+                                &working_set,
+                                &pipeline_element.expr,
+                                flat.0.start,
+                            );
+                            let mut completer = VariableCompletion::new(
+                                most_left_var.unwrap_or((vec![], vec![])),
+                                closure_params,
+                            );
 
                             return self.process_completion(
                                 &mut completer,
                                 &working_set,
                                 prefix,
                                 new_span,
-                                fake_offset,
+                                span_offset,
                                 pos,
                             );
                         }
 
+                        // Cell path into a literal, constant-foldable subexpression,
+                        // e.g. `({a: {b: 1}}).a.` suggesting `b`.
+                        if most_left_var.is_none() {
+                            if let Some((block_span, sublevels)) =
+                                most_left_literal_record_sublevels(
+                                    flat_idx,
+                                    &working_set,
+                                    flattened.clone(),
+                                )
+                            {
+                                if let Some(block_id) =
+                                    find_subexpression_block(&pipeline_element.expr, block_span.end)
+                                {
+                                    let mut completer =
+                                        LiteralCellPathCompletion::new(block_id, sublevels);
+
+                                    return self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                }
+                            }
+                        }
+
+                        // A standalone `--` terminates flag parsing per POSIX
+                        // convention: everything after it is positional, even
+                        // if it happens to start with `-`.
+                        let dash_dash_terminated = spans[..flat_idx].iter().any(|s| s == "--");
+
+                        // The call actually being completed, e.g. `tst`'s
+                        // call in `each { tst - }`, `if true { tst - }` or
+                        // `try { tst - }` -- not always `pipeline_element`
+                        // itself, since that's the outermost call even when
+                        // the cursor is inside a nested block argument.
+                        let innermost =
+                            innermost_call(working_set, &pipeline_element.expr, flat.0.start)
+                                .unwrap_or(&pipeline_element.expr);
+
+                        // If the immediately preceding token is a flag that
+                        // itself takes a value (e.g. `--color` in `grep
+                        // --color -`), this token IS that value, not a new
+                        // flag position, even though it happens to start
+                        // with `-` too (e.g. a value that's itself a flag
+                        // name, or a negative number).
+                        let previous_flag_expects_value = flat_idx
+                            .checked_sub(1)
+                            .and_then(|idx| spans.get(idx))
+                            .is_some_and(|previous| match &innermost.expr {
+                                Expr::Call(call) => flag_takes_value(
+                                    &working_set.get_decl(call.decl_id).signature(),
+                                    previous,
+                                ),
+                                _ => false,
+                            });
+
                         // Flags completion
-                        if prefix.starts_with(b"-") {
+                        if prefix.starts_with(b"-")
+                            && !dash_dash_terminated
+                            && !previous_flag_expects_value
+                        {
                             // Try to complete flag internally
-                            let mut completer = FlagCompletion::new(pipeline_element.expr.clone());
-                            let result = self.process_completion(
+                            let mut completer = FlagCompletion::new(innermost.clone());
+                            let mut result = self.process_completion(
                                 &mut completer,
                                 &working_set,
                                 prefix.clone(),
                                 new_span,
-                                fake_offset,
+                                span_offset,
                                 pos,
                             );
 
+                            // `cd -` (meaning `$env.OLDPWD`) looks like a flag
+                            // prefix, so make sure it isn't shadowed by `cd`'s
+                            // actual flags (e.g. `-h`).
+                            if spans.first().map(|s| s.as_str()) == Some("cd")
+                                && prefix == b"-"
+                                && self
+                                    .stack
+                                    .get_env_var(&self.engine_state, "OLDPWD")
+                                    .is_some()
+                            {
+                                result.push(SemanticSuggestion {
+                                    suggestion: Suggestion {
+                                        value: "-".into(),
+                                        description: Some(
+                                            "$env.OLDPWD (the previous working directory)".into(),
+                                        ),
+                                        style: None,
+                                        extra: None,
+                                        span: reedline::Span {
+                                            start: new_span.start - span_offset,
+                                            end: new_span.end - span_offset,
+                                        },
+                                        append_whitespace: false,
+                                    },
+                                    kind: None,
+                                    match_score: None,
+                                    is_unambiguous_match: false,
+                                });
+                            }
+
                             if !result.is_empty() {
                                 return result;
                             }
 
                             // We got no results for internal completion
                             // now we can check if external completer is set and use it
-                            if let Some(closure) = config.external_completer.as_ref() {
-                                if let Some(external_result) =
-                                    self.external_completion(closure, &spans, fake_offset, new_span)
-                                {
-                                    return external_result;
+                            if !self.disabled.external {
+                                if let Some(closure) = config.external_completer.as_ref() {
+                                    let custom_start = Instant::now();
+                                    let external_result = self.external_completion_with_fallback(
+                                        closure,
+                                        &spans,
+                                        &working_set,
+                                        prefix.clone(),
+                                        span_offset,
+                                        new_span,
+                                        pos,
+                                    );
+                                    metrics.custom_completer_time += custom_start.elapsed();
+                                    if let Some(external_result) = external_result {
+                                        return external_result;
+                                    }
                                 }
                             }
                         }
@@ -237,7 +1049,7 @@ impl NuCompleter {
                                 &working_set,
                                 prefix,
                                 new_span,
-                                fake_offset,
+                                span_offset,
                                 pos,
                             );
                         }
@@ -249,77 +1061,464 @@ impl NuCompleter {
                                 let prev_expr_str =
                                     working_set.get_span_contents(previous_expr.0).to_vec();
 
-                                // Completion for .nu files
-                                if prev_expr_str == b"use"
-                                    || prev_expr_str == b"overlay use"
-                                    || prev_expr_str == b"source-env"
+                                // A boolean switch can be given an explicit
+                                // value with `--flag=`, e.g. `--verbose=true`.
+                                // The value fails to parse as a literal until
+                                // it's fully typed, so it can't be recognized
+                                // by its own flattened shape; look at what
+                                // immediately precedes it instead.
+                                if prev_expr_str.starts_with(b"--")
+                                    && working_set.get_span_contents(Span::new(
+                                        previous_expr.0.end,
+                                        flat.0.start,
+                                    )) == b"="
+                                {
+                                    if let Expr::Call(call) = &pipeline_element.expr.expr {
+                                        let sig = working_set.get_decl(call.decl_id).signature();
+                                        let long_name = &prev_expr_str[2..];
+                                        let is_switch = sig.named.iter().any(|named| {
+                                            named.long.as_bytes() == long_name
+                                                && named.arg.is_none()
+                                        });
+                                        if is_switch {
+                                            let mut completer = BooleanCompletion::new();
+
+                                            return self.process_completion(
+                                                &mut completer,
+                                                &working_set,
+                                                prefix,
+                                                new_span,
+                                                span_offset,
+                                                pos,
+                                            );
+                                        }
+                                    }
+                                }
+
+                                // `overlay hide <tab>`: suggest the names of
+                                // the overlays that are currently active,
+                                // since those are the only valid arguments.
+                                if prev_expr_str == b"overlay hide" {
+                                    let mut completer = OverlayCompletion::new();
+
+                                    return self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                }
+
+                                // Completion for .nu files
+                                if prev_expr_str == b"use"
+                                    || prev_expr_str == b"overlay use"
+                                    || prev_expr_str == b"source-env"
+                                {
+                                    let mut completer = DotNuCompletion::new();
+
+                                    return self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                } else if matches!(
+                                    spans.first().map(String::as_str),
+                                    Some("use") | Some("overlay use")
+                                ) {
+                                    // `use <module> <tab>` / `overlay use <module> <tab>`:
+                                    // suggest the module's own exported commands,
+                                    // aliases, and constants instead of falling
+                                    // through to plain filesystem completion.
+                                    if let Some(module_id) = working_set.find_module(&prev_expr_str)
+                                    {
+                                        let mut completer = ModuleMemberCompletion::new(module_id);
+
+                                        return self.process_completion(
+                                            &mut completer,
+                                            &working_set,
+                                            prefix,
+                                            new_span,
+                                            span_offset,
+                                            pos,
+                                        );
+                                    }
+                                } else if prev_expr_str == b"ls" {
+                                    if self.disabled.file {
+                                        return vec![];
+                                    }
+                                    let mut completer = spans
+                                        .first()
+                                        .map(|name| self.file_completer_for(name))
+                                        .unwrap_or_default();
+
+                                    let scan_start = Instant::now();
+                                    let result = self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                    metrics.file_scan_time += scan_start.elapsed();
+                                    return result;
+                                } else if prev_expr_str == b"help" {
+                                    // `help <tab>`: suggest command names, since
+                                    // `help <name>` shows that command's help,
+                                    // plus the built-in subtopics (`help
+                                    // commands`, `help modules`, ...).
+                                    let mut completer = HelpCompletion::new();
+
+                                    return self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                } else if prev_expr_str == b"get"
+                                    || prev_expr_str == b"select"
+                                    || prev_expr_str == b"where"
+                                {
+                                    // Cell path argument: suggest the columns the
+                                    // previous pipeline element is declared to output,
+                                    // e.g. `ls | get <tab>` -> name, type, size, ...
+                                    // `where` uses the same columns as the row
+                                    // condition's left-hand side, e.g.
+                                    // `ls | where <tab>` -> name, type, size, ...
+                                    //
+                                    // The enclosing pipeline may be nested inside a
+                                    // string interpolation, e.g. `$"(ls | get na)"`,
+                                    // in which case it isn't `pipeline`/`element_idx`
+                                    // (those describe the outermost pipeline) but
+                                    // one reached by descending into the
+                                    // interpolation's subexpression.
+                                    let (enclosing_elements, enclosing_idx) =
+                                        find_enclosing_pipeline(
+                                            &working_set,
+                                            &pipeline_element.expr,
+                                            flat.0.start,
+                                        )
+                                        .unwrap_or((&pipeline.elements, element_idx));
+
+                                    if let Some(columns) = enclosing_idx
+                                        .checked_sub(1)
+                                        .and_then(|i| enclosing_elements.get(i))
+                                        .and_then(|prev_element| {
+                                            previous_command_output_columns(
+                                                &prev_element.expr,
+                                                &working_set,
+                                            )
+                                        })
+                                    {
+                                        let mut completer = PipelineOutputCompletion::new(columns);
+                                        let result = self.process_completion(
+                                            &mut completer,
+                                            &working_set,
+                                            prefix.clone(),
+                                            new_span,
+                                            span_offset,
+                                            pos,
+                                        );
+
+                                        if !result.is_empty() {
+                                            return result;
+                                        }
+                                    }
+                                } else if prev_expr_str == b"=" && flat_idx >= 3 {
+                                    // Complete known-enum config values, e.g.
+                                    // `$env.config.completions.algorithm = <tab>`
+                                    let path = vec![
+                                        working_set
+                                            .get_span_contents(flattened[flat_idx - 3].0)
+                                            .to_vec(),
+                                        working_set
+                                            .get_span_contents(flattened[flat_idx - 2].0)
+                                            .to_vec(),
+                                    ];
+                                    let mut completer = ConfigValueCompletion::new(path);
+                                    let result = self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix.clone(),
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+
+                                    if !result.is_empty() {
+                                        return result;
+                                    }
+                                } else if let Some(module_id) =
+                                    working_set.find_module(&prev_expr_str)
+                                {
+                                    // The previous word names a known module (e.g. `std `):
+                                    // suggest its members instead of falling through to the
+                                    // full, unfiltered command list, the same way `use std
+                                    // <tab>` does. Subcommands that are already registered
+                                    // as their own fully qualified decl (e.g. `std log
+                                    // info`) are still reachable through the normal command
+                                    // completion below once enough of the name is typed.
+                                    let mut completer = ModuleMemberCompletion::new(module_id);
+                                    let result = self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix.clone(),
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+
+                                    if !result.is_empty() {
+                                        return result;
+                                    }
+                                } else if is_operand_shape(&previous_expr.1) {
+                                    // Word-operators (`in`, `not-in`, `starts-with`, ...)
+                                    // that can follow an operand in expression position
+                                    let mut completer =
+                                        OperatorCompletion::new(previous_expr.1.clone());
+
+                                    let result = self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix.clone(),
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+
+                                    if !result.is_empty() {
+                                        return result;
+                                    }
+                                }
+                            }
+                        }
+
+                        // A bare number typed under a `duration`- or
+                        // `filesize`-shaped positional (e.g. `sleep 5<tab>`)
+                        // doesn't parse on its own -- it's missing its unit
+                        // suffix -- so it flattens as `FlatShape::Garbage`
+                        // rather than anything unit-aware. Look up what the
+                        // call actually expects there and, if it's one of
+                        // these two shapes, suggest completing the number
+                        // with each valid unit instead of offering nothing.
+                        let expected_shape = if flat.1 == FlatShape::Garbage {
+                            find_expected_positional_shape(
+                                &working_set,
+                                &pipeline_element.expr,
+                                flat.0.start,
+                            )
+                        } else {
+                            None
+                        };
+
+                        if let Some(mut completer) = match expected_shape {
+                            Some(SyntaxShape::Duration) => Some(UnitSuffixCompletion::duration()),
+                            Some(SyntaxShape::Filesize) => Some(UnitSuffixCompletion::filesize()),
+                            _ => None,
+                        } {
+                            let result = self.process_completion(
+                                &mut completer,
+                                &working_set,
+                                prefix.clone(),
+                                new_span,
+                                span_offset,
+                                pos,
+                            );
+
+                            if !result.is_empty() {
+                                return result;
+                            }
+                        }
+
+                        // A bare `{ ` (or a partially typed key) inside a
+                        // record literal has no `:` yet, so `parse_record`
+                        // can't build a real key expression for it either --
+                        // it flattens as `FlatShape::Garbage`, same as the
+                        // duration/filesize case above. If the argument's
+                        // declared shape is `record<...>`, offer its field
+                        // names there, skipping any already set in this
+                        // literal.
+                        if flat.1 == FlatShape::Garbage {
+                            if let Some((record_expr, fields)) = find_expected_record_positional(
+                                &working_set,
+                                &pipeline_element.expr,
+                                flat.0.start,
+                            ) {
+                                let already_set = record_keys_already_set(record_expr);
+                                let mut completer = RecordKeyCompletion::new(
+                                    fields
+                                        .into_iter()
+                                        .map(|(name, _)| name)
+                                        .filter(|name| !already_set.contains(name))
+                                        .collect(),
+                                );
+
+                                let result = self.process_completion(
+                                    &mut completer,
+                                    &working_set,
+                                    prefix.clone(),
+                                    new_span,
+                                    span_offset,
+                                    pos,
+                                );
+
+                                if !result.is_empty() {
+                                    return result;
+                                }
+                            }
+                        }
+
+                        // Match other types
+                        match &flat.1 {
+                            FlatShape::Custom(decl_id) => {
+                                let mut completer = CustomCompletion::new(
+                                    self.stack.clone(),
+                                    *decl_id,
+                                    initial_line,
+                                );
+
+                                let custom_start = Instant::now();
+                                let result = self.process_completion(
+                                    &mut completer,
+                                    &working_set,
+                                    prefix,
+                                    new_span,
+                                    span_offset,
+                                    pos,
+                                );
+                                metrics.custom_completer_time += custom_start.elapsed();
+                                return result;
+                            }
+                            FlatShape::Bool => {
+                                let mut completer = BooleanCompletion::new();
+
+                                return self.process_completion(
+                                    &mut completer,
+                                    &working_set,
+                                    prefix,
+                                    new_span,
+                                    span_offset,
+                                    pos,
+                                );
+                            }
+                            FlatShape::MatchPattern => {
+                                // Conservative: only a subject with a known
+                                // finite set of values (currently just
+                                // `bool`) gets its literal patterns
+                                // suggested, e.g. `match true { <tab> }`.
+                                if find_match_subject_type(
+                                    &working_set,
+                                    &pipeline_element.expr,
+                                    new_span.start,
+                                ) == Some(Type::Bool)
                                 {
-                                    let mut completer = DotNuCompletion::new();
+                                    let mut completer = BooleanCompletion::new();
 
                                     return self.process_completion(
                                         &mut completer,
                                         &working_set,
                                         prefix,
                                         new_span,
-                                        fake_offset,
+                                        span_offset,
                                         pos,
                                     );
-                                } else if prev_expr_str == b"ls" {
-                                    let mut completer = FileCompletion::new();
+                                }
 
-                                    return self.process_completion(
+                                return vec![];
+                            }
+                            FlatShape::Directory => {
+                                if self.disabled.directory {
+                                    return vec![];
+                                }
+
+                                // Before anything's been typed, `cd`'s recent
+                                // directories (`$env.DIRS_LIST`) take
+                                // priority over the plain directory listing.
+                                if spans.first().map(|s| s.as_str()) == Some("cd") && prefix.is_empty()
+                                {
+                                    let mut completer = RecentDirectoryCompletion::new();
+                                    let result = self.process_completion(
                                         &mut completer,
                                         &working_set,
-                                        prefix,
+                                        prefix.clone(),
                                         new_span,
-                                        fake_offset,
+                                        span_offset,
                                         pos,
                                     );
+                                    if !result.is_empty() {
+                                        return result;
+                                    }
                                 }
-                            }
-                        }
 
-                        // Match other types
-                        match &flat.1 {
-                            FlatShape::Custom(decl_id) => {
-                                let mut completer = CustomCompletion::new(
-                                    self.stack.clone(),
-                                    *decl_id,
-                                    initial_line,
-                                );
+                                let mut completer = DirectoryCompletion::new();
 
-                                return self.process_completion(
+                                let scan_start = Instant::now();
+                                let result = self.process_completion(
                                     &mut completer,
                                     &working_set,
                                     prefix,
                                     new_span,
-                                    fake_offset,
+                                    span_offset,
                                     pos,
                                 );
+                                metrics.file_scan_time += scan_start.elapsed();
+                                return result;
                             }
-                            FlatShape::Directory => {
-                                let mut completer = DirectoryCompletion::new();
+                            FlatShape::Filepath | FlatShape::GlobPattern => {
+                                if self.disabled.file {
+                                    return vec![];
+                                }
+                                let mut completer = spans
+                                    .first()
+                                    .map(|name| self.file_completer_for(name))
+                                    .unwrap_or_default();
 
-                                return self.process_completion(
+                                let scan_start = Instant::now();
+                                let result = self.process_completion(
                                     &mut completer,
                                     &working_set,
                                     prefix,
                                     new_span,
-                                    fake_offset,
+                                    span_offset,
                                     pos,
                                 );
+                                metrics.file_scan_time += scan_start.elapsed();
+                                return result;
                             }
-                            FlatShape::Filepath | FlatShape::GlobPattern => {
-                                let mut completer = FileCompletion::new();
+                            // `^sl` (a bare name) should complete PATH
+                            // executables, same as an ordinary command name,
+                            // but `^./sl` or `^/usr/bin/sl` names a path the
+                            // user is navigating in the filesystem, where a
+                            // PATH-executable-name search can never match.
+                            FlatShape::External
+                                if prefix.iter().any(|&b| is_separator(b as char)) =>
+                            {
+                                if self.disabled.file {
+                                    return vec![];
+                                }
+                                let mut completer = spans
+                                    .first()
+                                    .map(|name| self.file_completer_for(name))
+                                    .unwrap_or_default();
 
-                                return self.process_completion(
+                                let scan_start = Instant::now();
+                                let result = self.process_completion(
                                     &mut completer,
                                     &working_set,
                                     prefix,
                                     new_span,
-                                    fake_offset,
+                                    span_offset,
                                     pos,
                                 );
+                                metrics.file_scan_time += scan_start.elapsed();
+                                return result;
                             }
                             flat_shape => {
                                 let mut completer = CommandCompletion::new(
@@ -334,7 +1533,7 @@ impl NuCompleter {
                                     &working_set,
                                     prefix.clone(),
                                     new_span,
-                                    fake_offset,
+                                    span_offset,
                                     pos,
                                 );
 
@@ -343,30 +1542,51 @@ impl NuCompleter {
                                 }
 
                                 // Try to complete using an external completer (if set)
-                                if let Some(closure) = config.external_completer.as_ref() {
-                                    if let Some(external_result) = self.external_completion(
-                                        closure,
-                                        &spans,
-                                        fake_offset,
-                                        new_span,
-                                    ) {
-                                        return external_result;
+                                if !self.disabled.external {
+                                    if let Some(closure) = config.external_completer.as_ref() {
+                                        let custom_start = Instant::now();
+                                        let external_result = self
+                                            .external_completion_with_fallback(
+                                                closure,
+                                                &spans,
+                                                &working_set,
+                                                prefix.clone(),
+                                                span_offset,
+                                                new_span,
+                                                pos,
+                                            );
+                                        metrics.custom_completer_time += custom_start.elapsed();
+                                        if let Some(external_result) = external_result {
+                                            return external_result;
+                                        }
                                     }
                                 }
 
-                                // Check for file completion
-                                let mut completer = FileCompletion::new();
-                                out = self.process_completion(
-                                    &mut completer,
-                                    &working_set,
-                                    prefix,
-                                    new_span,
-                                    fake_offset,
-                                    pos,
-                                );
+                                // Check for file completion. A `string`-typed
+                                // positional (`FlatShape::String`) is left
+                                // out on purpose: unlike an untyped/external
+                                // argument, it explicitly opted out of
+                                // filesystem-shaped values, so it shouldn't
+                                // fall back to suggesting paths either.
+                                if !self.disabled.file && !matches!(flat_shape, FlatShape::String) {
+                                    let mut completer = spans
+                                        .first()
+                                        .map(|name| self.file_completer_for(name))
+                                        .unwrap_or_default();
+                                    let scan_start = Instant::now();
+                                    out = self.process_completion(
+                                        &mut completer,
+                                        &working_set,
+                                        prefix,
+                                        new_span,
+                                        span_offset,
+                                        pos,
+                                    );
+                                    metrics.file_scan_time += scan_start.elapsed();
 
-                                if !out.is_empty() {
-                                    return out;
+                                    if !out.is_empty() {
+                                        return out;
+                                    }
                                 }
                             }
                         };
@@ -388,6 +1608,25 @@ impl ReedlineCompleter for NuCompleter {
     }
 }
 
+// Whether a flattened shape represents a value that a word-operator
+// (`in`, `not-in`, `starts-with`, ...) could meaningfully follow.
+fn is_operand_shape(shape: &FlatShape) -> bool {
+    matches!(
+        shape,
+        FlatShape::String
+            | FlatShape::RawString
+            | FlatShape::StringInterpolation
+            | FlatShape::Int
+            | FlatShape::Float
+            | FlatShape::Bool
+            | FlatShape::DateTime
+            | FlatShape::Variable(_)
+            | FlatShape::List
+            | FlatShape::Table
+            | FlatShape::Record
+    )
+}
+
 // reads the most left variable returning it's name (e.g: $myvar)
 // and the depth (a.b.c)
 fn most_left_variable(
@@ -438,6 +1677,489 @@ fn most_left_variable(
     Some((var, sublevels))
 }
 
+/// Recovers a `(head variable, sublevels)` context from a spread argument's
+/// raw token text, e.g. `...$rec.a.` -- along with how many leading bytes
+/// belong to the `...` marker and the already-typed path segments, as
+/// opposed to the in-progress final member. Returns `None` for anything that
+/// doesn't look like a spread-prefixed variable (`...[`, `...(`, or a bare
+/// positional argument all fall through here).
+fn spread_variable_context(prefix: &[u8]) -> Option<(usize, (Vec<u8>, Vec<Vec<u8>>))> {
+    let rest = prefix.strip_prefix(b"...")?;
+    if !rest.starts_with(b"$") {
+        return None;
+    }
+
+    let mut parts = rest.split(|&b| b == b'.');
+    let head = parts.next()?.to_vec();
+    let mut parts: Vec<&[u8]> = parts.collect();
+
+    let Some(last) = parts.pop() else {
+        // No `.` typed yet -- still completing the variable's own name.
+        return Some((3, (vec![], vec![])));
+    };
+
+    let stripped_len = prefix.len() - last.len();
+    let sublevels = parts.into_iter().map(|s| s.to_vec()).collect();
+    Some((stripped_len, (head, sublevels)))
+}
+
+/// Looks backwards from `idx` for a `(...)` subexpression immediately
+/// followed by a run of string cell-path members, e.g. `({a: 1}).a.b`, the
+/// same shape [`most_left_variable`] detects for `$var.a.b` but rooted at a
+/// parenthesized block instead of a variable. Returns the span of the
+/// closing `)` (which is also the end of the `Expr::Subexpression`, letting
+/// the caller locate the block in the parsed AST) and the sublevels typed
+/// after it.
+fn most_left_literal_record_sublevels(
+    idx: usize,
+    working_set: &StateWorkingSet<'_>,
+    flattened: Vec<(Span, FlatShape)>,
+) -> Option<(Span, Vec<Vec<u8>>)> {
+    let mut rev = flattened;
+    rev.truncate(idx);
+    rev = rev.into_iter().rev().collect();
+
+    let mut sublevels_found: Vec<Vec<u8>> = vec![];
+    let mut block_span = None;
+    for item in rev {
+        match item.1 {
+            FlatShape::Block => {
+                block_span = Some(item.0);
+                break;
+            }
+            FlatShape::String => {
+                sublevels_found.push(working_set.get_span_contents(item.0).to_vec());
+            }
+            _ => break,
+        }
+    }
+
+    let block_span = block_span?;
+    sublevels_found.reverse();
+    Some((block_span, sublevels_found))
+}
+
+/// Recursively searches an expression tree for an `Expr::Subexpression` whose
+/// span ends at `end` (the closing `)` recovered from the flattened tokens),
+/// returning its block id.
+fn find_subexpression_block(expr: &Expression, end: usize) -> Option<BlockId> {
+    if let Expr::Subexpression(block_id) = &expr.expr {
+        if expr.span.end == end {
+            return Some(*block_id);
+        }
+    }
+
+    match &expr.expr {
+        Expr::FullCellPath(cell_path) => find_subexpression_block(&cell_path.head, end),
+        Expr::Call(call) => call.arguments.iter().find_map(|arg| match arg {
+            Argument::Positional(expr)
+            | Argument::Unknown(expr)
+            | Argument::Spread(expr)
+            | Argument::Named((_, _, Some(expr))) => find_subexpression_block(expr, end),
+            Argument::Named((_, _, None)) => None,
+        }),
+        Expr::BinaryOp(lhs, _, rhs) => {
+            find_subexpression_block(lhs, end).or_else(|| find_subexpression_block(rhs, end))
+        }
+        _ => None,
+    }
+}
+
+/// Recursively searches an expression tree for a `match $subject { ... }`
+/// call whose pattern block spans `target`, returning the subject's
+/// inferred type. Used to decide which literal patterns can be suggested,
+/// e.g. `true`/`false` for a `bool` subject.
+fn find_match_subject_type(
+    working_set: &StateWorkingSet,
+    expr: &Expression,
+    target: usize,
+) -> Option<Type> {
+    if let Expr::Call(call) = &expr.expr {
+        if working_set.get_decl(call.decl_id).name() == "match" {
+            if let (Some(Argument::Positional(subject)), Some(Argument::Positional(match_block))) =
+                (call.arguments.first(), call.arguments.get(1))
+            {
+                if matches!(match_block.expr, Expr::MatchBlock(_))
+                    && match_block.span.start <= target
+                    && target <= match_block.span.end
+                {
+                    return Some(subject.ty.clone());
+                }
+            }
+        }
+
+        return call.arguments.iter().find_map(|arg| match arg {
+            Argument::Positional(expr)
+            | Argument::Unknown(expr)
+            | Argument::Spread(expr)
+            | Argument::Named((_, _, Some(expr))) => {
+                find_match_subject_type(working_set, expr, target)
+            }
+            Argument::Named((_, _, None)) => None,
+        });
+    }
+
+    match &expr.expr {
+        Expr::FullCellPath(cell_path) => {
+            find_match_subject_type(working_set, &cell_path.head, target)
+        }
+        Expr::BinaryOp(lhs, _, rhs) => find_match_subject_type(working_set, lhs, target)
+            .or_else(|| find_match_subject_type(working_set, rhs, target)),
+        Expr::Subexpression(block_id) | Expr::Block(block_id) | Expr::Closure(block_id) => {
+            let block = working_set.get_block(*block_id);
+            block.pipelines.iter().find_map(|pipeline| {
+                pipeline
+                    .elements
+                    .iter()
+                    .find_map(|element| find_match_subject_type(working_set, &element.expr, target))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Whether `token` names a flag in `sig` that takes a value, e.g. `--color`
+/// in a signature declaring `--color: string` (as opposed to a switch like
+/// `--help`, which never consumes the next token). A short flag is checked
+/// by its last character, so it still matches inside an already-typed
+/// switch cluster like `-al` (in that case there's no value-taking flag to
+/// find, since clusters only ever chain switches).
+fn flag_takes_value(sig: &Signature, token: &str) -> bool {
+    let Some(flag_text) = token.strip_prefix('-') else {
+        return false;
+    };
+
+    if let Some(long) = flag_text.strip_prefix('-') {
+        return sig.named.iter().any(|f| f.long == long && f.arg.is_some());
+    }
+
+    match flag_text.chars().last() {
+        Some(short) => sig
+            .named
+            .iter()
+            .any(|f| f.short == Some(short) && f.arg.is_some()),
+        None => false,
+    }
+}
+
+/// Finds the innermost call expression containing `target`, descending
+/// through any block-bearing argument along the way (`each { ... }`, `do {
+/// ... }`, `if ... { ... }`/`else { ... }`, `try { ... } catch { ... }`, ...)
+/// instead of stopping at the outermost call. This is what lets flag
+/// completion see the call actually being typed, e.g. `tst -` inside `each
+/// { tst - }`, rather than always resolving to the enclosing `each` call.
+fn innermost_call<'a>(
+    working_set: &'a StateWorkingSet,
+    expr: &'a Expression,
+    target: usize,
+) -> Option<&'a Expression> {
+    if !(expr.span.start <= target && target <= expr.span.end) {
+        return None;
+    }
+
+    match &expr.expr {
+        Expr::Call(call) => {
+            let inner = call.arguments.iter().find_map(|arg| match arg {
+                Argument::Positional(arg_expr)
+                | Argument::Unknown(arg_expr)
+                | Argument::Spread(arg_expr)
+                | Argument::Named((_, _, Some(arg_expr))) => {
+                    innermost_call(working_set, arg_expr, target)
+                }
+                Argument::Named((_, _, None)) => None,
+            });
+            inner.or(Some(expr))
+        }
+        Expr::Keyword(keyword) => innermost_call(working_set, &keyword.expr, target),
+        Expr::Subexpression(block_id) | Expr::Block(block_id) | Expr::Closure(block_id) => {
+            let block = working_set.get_block(*block_id);
+            block.pipelines.iter().find_map(|pipeline| {
+                pipeline
+                    .elements
+                    .iter()
+                    .find_map(|element| innermost_call(working_set, &element.expr, target))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Recursively searches a call's positional arguments for the one spanning
+/// `target`, returning its *declared* [`SyntaxShape`] from the callee's
+/// signature (as opposed to the argument expression's own, possibly-`Any`,
+/// inferred type -- the whole point is finding the shape for an argument
+/// that failed to parse into anything meaningful yet). Positional arguments
+/// are matched to `required_positional`, then `optional_positional`, then
+/// `rest_positional`, in the same order the parser itself fills them.
+fn find_expected_positional_shape(
+    working_set: &StateWorkingSet,
+    expr: &Expression,
+    target: usize,
+) -> Option<SyntaxShape> {
+    let Expr::Call(call) = &expr.expr else {
+        return None;
+    };
+
+    let mut positional_idx = 0;
+    for arg in &call.arguments {
+        match arg {
+            Argument::Positional(arg_expr) | Argument::Unknown(arg_expr) => {
+                if arg_expr.span.start <= target && target <= arg_expr.span.end {
+                    let sig = working_set.get_decl(call.decl_id).signature();
+                    return sig
+                        .required_positional
+                        .iter()
+                        .chain(sig.optional_positional.iter())
+                        .nth(positional_idx)
+                        .map(|arg| arg.shape.clone())
+                        .or_else(|| sig.rest_positional.as_ref().map(|arg| arg.shape.clone()));
+                }
+                if let Some(shape) = find_expected_positional_shape(working_set, arg_expr, target)
+                {
+                    return Some(shape);
+                }
+                positional_idx += 1;
+            }
+            Argument::Spread(arg_expr) | Argument::Named((_, _, Some(arg_expr))) => {
+                if let Some(shape) = find_expected_positional_shape(working_set, arg_expr, target)
+                {
+                    return Some(shape);
+                }
+            }
+            Argument::Named((_, _, None)) => {}
+        }
+    }
+
+    None
+}
+
+/// Like [`find_expected_positional_shape`], but for `record<...>`-shaped
+/// positionals specifically: also returns the record literal expression
+/// itself, so [`record_keys_already_set`] can exclude keys the user typed
+/// there already.
+fn find_expected_record_positional<'a>(
+    working_set: &StateWorkingSet,
+    expr: &'a Expression,
+    target: usize,
+) -> Option<(&'a Expression, Vec<(String, SyntaxShape)>)> {
+    let Expr::Call(call) = &expr.expr else {
+        return None;
+    };
+
+    let mut positional_idx = 0;
+    for arg in &call.arguments {
+        match arg {
+            Argument::Positional(arg_expr) | Argument::Unknown(arg_expr) => {
+                if arg_expr.span.start <= target && target <= arg_expr.span.end {
+                    let sig = working_set.get_decl(call.decl_id).signature();
+                    let shape = sig
+                        .required_positional
+                        .iter()
+                        .chain(sig.optional_positional.iter())
+                        .nth(positional_idx)
+                        .map(|arg| arg.shape.clone())
+                        .or_else(|| sig.rest_positional.as_ref().map(|arg| arg.shape.clone()));
+                    return match shape {
+                        Some(SyntaxShape::Record(fields)) => Some((arg_expr, fields)),
+                        _ => None,
+                    };
+                }
+                if let Some(found) =
+                    find_expected_record_positional(working_set, arg_expr, target)
+                {
+                    return Some(found);
+                }
+                positional_idx += 1;
+            }
+            Argument::Spread(arg_expr) | Argument::Named((_, _, Some(arg_expr))) => {
+                if let Some(found) =
+                    find_expected_record_positional(working_set, arg_expr, target)
+                {
+                    return Some(found);
+                }
+            }
+            Argument::Named((_, _, None)) => {}
+        }
+    }
+
+    None
+}
+
+/// The keys already present in a (possibly still being typed) record
+/// literal, so [`RecordKeyCompletion`] doesn't re-suggest a field the user
+/// already set. Only literal string/bareword keys are recognized; a
+/// spread (`...$rec`) or computed key contributes nothing, since there's no
+/// way to know its value without running the expression.
+fn record_keys_already_set(expr: &Expression) -> Vec<String> {
+    let Expr::Record(list) = &expr.expr else {
+        return vec![];
+    };
+
+    list.iter()
+        .filter_map(|item| match item {
+            RecordItem::Pair(key, _) => match &key.expr {
+                Expr::String(s) => Some(s.clone()),
+                _ => None,
+            },
+            RecordItem::Spread(_, _) => None,
+        })
+        .collect()
+}
+
+/// Recursively searches an expression tree for the innermost pipeline (a
+/// `Block`, `Closure` or `Subexpression`, including one reached through a
+/// string interpolation or nested as a call argument) whose elements contain
+/// `target`, returning that pipeline's elements together with the index of
+/// the containing element. This lets pipeline-context completions (e.g.
+/// `get`/`select`/`where` looking up the previous element's output columns)
+/// see through nesting instead of only ever considering the outermost
+/// pipeline, e.g. resolving `ls` as the previous element of `get` inside
+/// `$"(ls | get na)"` or `cp (ls | get na)`.
+fn find_enclosing_pipeline<'a>(
+    working_set: &'a StateWorkingSet,
+    expr: &Expression,
+    target: usize,
+) -> Option<(&'a [PipelineElement], usize)> {
+    match &expr.expr {
+        Expr::Subexpression(block_id) | Expr::Block(block_id) | Expr::Closure(block_id) => {
+            let block = working_set.get_block(*block_id);
+            block.pipelines.iter().find_map(|pipeline| {
+                pipeline
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .find_map(|(idx, element)| {
+                        if element.expr.span.start <= target && target <= element.expr.span.end {
+                            Some(
+                                find_enclosing_pipeline(working_set, &element.expr, target)
+                                    .unwrap_or((&pipeline.elements, idx)),
+                            )
+                        } else {
+                            None
+                        }
+                    })
+            })
+        }
+        Expr::StringInterpolation(exprs) => exprs
+            .iter()
+            .find_map(|e| find_enclosing_pipeline(working_set, e, target)),
+        Expr::FullCellPath(cell_path) => {
+            find_enclosing_pipeline(working_set, &cell_path.head, target)
+        }
+        Expr::Call(call) => call.arguments.iter().find_map(|arg| match arg {
+            Argument::Positional(expr)
+            | Argument::Unknown(expr)
+            | Argument::Spread(expr)
+            | Argument::Named((_, _, Some(expr))) => {
+                find_enclosing_pipeline(working_set, expr, target)
+            }
+            Argument::Named((_, _, None)) => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Recursively searches an expression tree for every closure enclosing
+/// `target` (including one reached through a call argument, string
+/// interpolation, or nested inside another closure/block/subexpression),
+/// returning the `($name, VarId)` of each of their declared parameters.
+/// `working_set.delta.scope` only reflects a closure's parameter scope while
+/// the parser is still inside that closure's `parse_block` call, which has
+/// already returned by the time completion runs -- so a variable completer
+/// relying on it alone never sees `$spans` while typing `{|spans| $sp`.
+/// Walking the parsed closures directly instead finds those parameters
+/// regardless of parser scope lifetime.
+fn find_enclosing_closure_params(
+    working_set: &StateWorkingSet,
+    expr: &Expression,
+    target: usize,
+) -> Vec<(Vec<u8>, VarId)> {
+    let recurse_into_block = |block_id: &BlockId| {
+        let block = working_set.get_block(*block_id);
+        block
+            .pipelines
+            .iter()
+            .find_map(|pipeline| {
+                pipeline.elements.iter().find_map(|element| {
+                    if element.expr.span.start <= target && target <= element.expr.span.end {
+                        Some(find_enclosing_closure_params(
+                            working_set,
+                            &element.expr,
+                            target,
+                        ))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .unwrap_or_default()
+    };
+
+    match &expr.expr {
+        Expr::Closure(block_id) => {
+            let block = working_set.get_block(*block_id);
+            let mut params: Vec<(Vec<u8>, VarId)> = block
+                .signature
+                .required_positional
+                .iter()
+                .chain(block.signature.optional_positional.iter())
+                .chain(block.signature.rest_positional.iter())
+                .filter_map(|arg| arg.var_id.map(|var_id| (arg.name.clone().into_bytes(), var_id)))
+                .collect();
+
+            params.extend(recurse_into_block(block_id));
+            params
+        }
+        Expr::Subexpression(block_id) | Expr::Block(block_id) => recurse_into_block(block_id),
+        Expr::StringInterpolation(exprs) => exprs
+            .iter()
+            .flat_map(|e| find_enclosing_closure_params(working_set, e, target))
+            .collect(),
+        Expr::FullCellPath(cell_path) => {
+            find_enclosing_closure_params(working_set, &cell_path.head, target)
+        }
+        Expr::Call(call) => call
+            .arguments
+            .iter()
+            .flat_map(|arg| match arg {
+                Argument::Positional(expr)
+                | Argument::Unknown(expr)
+                | Argument::Spread(expr)
+                | Argument::Named((_, _, Some(expr))) => {
+                    find_enclosing_closure_params(working_set, expr, target)
+                }
+                Argument::Named((_, _, None)) => vec![],
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Best-effort lookup of the column names a pipeline element is declared to
+/// output, used to complete a following `get`/`select` cell path without
+/// running the pipeline. Only fires when the element is a direct command
+/// call whose declared output type is a non-empty `Table`/`Record` type;
+/// falls back to `None` (no suggestions) for `Any` or otherwise unknown output.
+fn previous_command_output_columns(
+    expr: &Expression,
+    working_set: &StateWorkingSet,
+) -> Option<Vec<String>> {
+    let Expr::Call(call) = &expr.expr else {
+        return None;
+    };
+    let decl = working_set.get_decl(call.decl_id);
+
+    decl.signature()
+        .input_output_types
+        .iter()
+        .find_map(|(_, output)| match output {
+            Type::Table(columns) | Type::Record(columns) if !columns.is_empty() => {
+                Some(columns.iter().map(|(name, _)| name.clone()).collect())
+            }
+            _ => None,
+        })
+}
+
 pub fn map_value_completions<'a>(
     list: impl Iterator<Item = &'a Value>,
     span: Span,
@@ -459,6 +2181,8 @@ pub fn map_value_completions<'a>(
                     append_whitespace: false,
                 },
                 kind: Some(SuggestionKind::Type(x.get_type())),
+                match_score: None,
+                is_unambiguous_match: false,
             });
         }
 
@@ -510,6 +2234,8 @@ pub fn map_value_completions<'a>(
             return Some(SemanticSuggestion {
                 suggestion,
                 kind: Some(SuggestionKind::Type(x.get_type())),
+                match_score: None,
+                is_unambiguous_match: false,
             });
         }
 
@@ -541,29 +2267,22 @@ mod completer_tests {
         );
 
         let mut completer = NuCompleter::new(engine_state.into(), Arc::new(Stack::new()));
+        // Command-name completion also matches a command's search terms
+        // (not just its name), so e.g. `l` surfaces `while` too, since one
+        // of its search terms is "loop".
         let dataset = [
-            ("sudo", false, "", Vec::new()),
-            ("sudo l", true, "l", vec!["ls", "let", "lines", "loop"]),
-            (" sudo", false, "", Vec::new()),
-            (" sudo le", true, "le", vec!["let", "length"]),
-            (
-                "ls | c",
-                true,
-                "c",
-                vec!["cd", "config", "const", "cp", "cal"],
-            ),
-            ("ls | sudo m", true, "m", vec!["mv", "mut", "move"]),
+            ("sudo", false, Vec::new()),
+            ("sudo l", true, vec!["ls", "let", "lines", "loop"]),
+            (" sudo", false, Vec::new()),
+            (" sudo le", true, vec!["let", "length"]),
+            ("ls | c", true, vec!["cd", "config", "const", "cp", "cal"]),
+            ("ls | sudo m", true, vec!["mv", "mut", "move"]),
         ];
-        for (line, has_result, begins_with, expected_values) in dataset {
+        for (line, has_result, expected_values) in dataset {
             let result = completer.completion_helper(line, line.len());
             // Test whether the result is empty or not
             assert_eq!(!result.is_empty(), has_result, "line: {}", line);
 
-            // Test whether the result begins with the expected value
-            result
-                .iter()
-                .for_each(|x| assert!(x.suggestion.value.starts_with(begins_with)));
-
             // Test whether the result contains all the expected values
             assert_eq!(
                 result
@@ -577,4 +2296,154 @@ mod completer_tests {
             );
         }
     }
+
+    #[test]
+    fn test_completion_operator_after_string_operand() {
+        let mut engine_state =
+            nu_command::add_shell_command_context(nu_cmd_lang::create_default_context());
+
+        let delta = {
+            let working_set = nu_protocol::engine::StateWorkingSet::new(&engine_state);
+            working_set.render()
+        };
+
+        let result = engine_state.merge_delta(delta);
+        assert!(
+            result.is_ok(),
+            "Error merging delta: {:?}",
+            result.err().unwrap()
+        );
+
+        let mut completer = NuCompleter::new(engine_state.into(), Arc::new(Stack::new()));
+        let line = "'foo' ";
+        let result = completer.completion_helper(line, line.len());
+
+        for expected in ["in", "not-in", "starts-with", "ends-with", "has"] {
+            assert!(
+                result.iter().any(|s| s.suggestion.value == expected),
+                "expected `{expected}` to be offered after a string operand, got: {:?}",
+                result
+                    .iter()
+                    .map(|s| &s.suggestion.value)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    fn suggestion(value: &str) -> SemanticSuggestion {
+        SemanticSuggestion {
+            suggestion: Suggestion {
+                value: value.to_string(),
+                ..Suggestion::default()
+            },
+            kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
+        }
+    }
+
+    #[test]
+    fn sort_smart_puts_prefix_matches_before_fuzzy_ones() {
+        let completer = NuCompleter::default();
+        let options = CompletionOptions::default();
+
+        let sorted = completer.sort_smart(
+            vec![suggestion("goodbye"), suggestion("git")],
+            b"gi",
+            &options,
+        );
+
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|s| &s.suggestion.value)
+                .collect::<Vec<_>>(),
+            vec!["git", "goodbye"]
+        );
+    }
+
+    #[test]
+    fn sort_smart_boosts_frequently_used_command_within_its_group() {
+        let mut completer = NuCompleter::default();
+        for _ in 0..5 {
+            completer.record_command_usage("grep");
+        }
+        let options = CompletionOptions::default();
+
+        // Both match the prefix, and "git" < "grep" alphabetically, but
+        // "grep" has been run more often, so it should win.
+        let sorted =
+            completer.sort_smart(vec![suggestion("git"), suggestion("grep")], b"g", &options);
+
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|s| &s.suggestion.value)
+                .collect::<Vec<_>>(),
+            vec!["grep", "git"]
+        );
+    }
+
+    #[test]
+    fn smart_sort_ranks_a_frequently_used_command_above_an_alphabetically_earlier_one() {
+        let mut engine_state =
+            nu_command::add_shell_command_context(nu_cmd_lang::create_default_context());
+
+        let delta = {
+            let working_set = nu_protocol::engine::StateWorkingSet::new(&engine_state);
+            working_set.render()
+        };
+        engine_state
+            .merge_delta(delta)
+            .expect("merging delta should succeed");
+
+        let mut config = engine_state.get_config().clone();
+        config.completion_sort = nu_protocol::CompletionSort::Smart;
+        engine_state.set_config(config);
+
+        let mut completer = NuCompleter::new(engine_state.into(), Arc::new(Stack::new()));
+        // "ls" would otherwise sort after "let" and "length" alphabetically,
+        // but reporting it as run repeatedly should move it to the front.
+        for _ in 0..10 {
+            completer.record_command_usage("ls");
+        }
+
+        let result = completer.completion_helper("l", 1);
+        let names: Vec<&str> = result.iter().map(|s| s.suggestion.value.as_str()).collect();
+
+        let ls_pos = names.iter().position(|n| *n == "ls");
+        let let_pos = names.iter().position(|n| *n == "let");
+        assert!(ls_pos.is_some() && let_pos.is_some());
+        assert!(ls_pos < let_pos, "expected ls before let, got: {:?}", names);
+    }
+
+    #[test]
+    fn test_completion_entry_style() {
+        let span = Span::test_data();
+        let values = vec![Value::test_record(nu_protocol::record! {
+            "value" => Value::test_string("rm"),
+            "style" => Value::test_record(nu_protocol::record! {
+                "fg" => Value::test_string("red"),
+            }),
+        })];
+
+        let suggestions = map_value_completions(values.iter(), span, 0);
+
+        assert_eq!(1, suggestions.len());
+        assert_eq!("rm", suggestions[0].suggestion.value);
+        assert!(suggestions[0].suggestion.style.is_some());
+    }
+
+    #[test]
+    fn test_completion_entry_without_style() {
+        let span = Span::test_data();
+        let values = vec![Value::test_record(nu_protocol::record! {
+            "value" => Value::test_string("ls"),
+        })];
+
+        let suggestions = map_value_completions(values.iter(), span, 0);
+
+        assert_eq!(1, suggestions.len());
+        assert!(suggestions[0].suggestion.style.is_none());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::completions::{matches, CompletionOptions};
+use crate::completions::{ignore_files, matches, matches_exactly, CompletionOptions};
 use nu_ansi_term::Style;
 use nu_engine::env_to_string;
 use nu_path::{expand_to_real_path, home_dir};
@@ -7,16 +7,56 @@ use nu_protocol::{
     Span,
 };
 use nu_utils::get_ls_colors;
-use std::path::{
-    is_separator, Component, Path, PathBuf, MAIN_SEPARATOR as SEP, MAIN_SEPARATOR_STR,
+use std::{
+    collections::HashMap,
+    path::{is_separator, Component, Path, PathBuf, MAIN_SEPARATOR as SEP, MAIN_SEPARATOR_STR},
+    sync::Arc,
 };
 
+/// Caches the directory entries read while completing a single partial path,
+/// keyed by the directory that was scanned. Candidate expansions like
+/// `rm par*` can otherwise `read_dir` the same directory more than once.
+type DirEntryCache = HashMap<PathBuf, Arc<Vec<(String, bool)>>>;
+
+fn read_dir_cached(cache: &mut DirEntryCache, path: &Path) -> Option<Arc<Vec<(String, bool)>>> {
+    if let Some(entries) = cache.get(path) {
+        return Some(Arc::clone(entries));
+    }
+
+    let entries: Vec<(String, bool)> = path
+        .read_dir()
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                // `Path::is_dir` follows symlinks, so a symlink pointing at a
+                // directory is classified as one (and gets the trailing
+                // separator/dir-only filtering that implies) rather than
+                // needing `entry.file_type()`, which wouldn't.
+                entry.path().is_dir(),
+            )
+        })
+        .collect();
+    let entries = Arc::new(entries);
+    cache.insert(path.to_path_buf(), Arc::clone(&entries));
+    Some(entries)
+}
+
 #[derive(Clone, Default)]
 pub struct PathBuiltFromString {
     parts: Vec<String>,
     isdir: bool,
 }
 
+/// A predicate consulted before completing a filesystem path, e.g. so a
+/// sandboxed embedder can restrict completions to an allowed root. An entry
+/// the predicate rejects is dropped from the results, and if it's a
+/// directory, traversal doesn't descend into it either. See
+/// `NuCompleter::set_path_filter`.
+pub type PathFilter = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+#[allow(clippy::too_many_arguments)]
 fn complete_rec(
     partial: &[&str],
     built: &PathBuiltFromString,
@@ -24,6 +64,8 @@ fn complete_rec(
     options: &CompletionOptions,
     dir: bool,
     isdir: bool,
+    cache: &mut DirEntryCache,
+    path_filter: Option<&PathFilter>,
 ) -> Vec<PathBuiltFromString> {
     let mut completions = vec![];
 
@@ -32,7 +74,7 @@ fn complete_rec(
             let mut built = built.clone();
             built.parts.push(base.to_string());
             built.isdir = true;
-            return complete_rec(rest, &built, cwd, options, dir, isdir);
+            return complete_rec(rest, &built, cwd, options, dir, isdir, cache, path_filter);
         }
     }
 
@@ -41,13 +83,34 @@ fn complete_rec(
         built_path.push(part);
     }
 
-    let Ok(result) = built_path.read_dir() else {
+    let Some(entries) = read_dir_cached(cache, &built_path) else {
         return completions;
     };
 
-    for entry in result.filter_map(|e| e.ok()) {
-        let entry_name = entry.file_name().to_string_lossy().into_owned();
-        let entry_isdir = entry.path().is_dir();
+    let typed_dot = partial.first().is_some_and(|base| base.starts_with('.'));
+    let ignore_rules = options
+        .use_ignore_files
+        .then(|| ignore_files::collect_ignore_rules(&built_path));
+
+    for (entry_name, entry_isdir) in entries.iter() {
+        if !options.hidden_files && entry_name.starts_with('.') && !typed_dot {
+            continue;
+        }
+
+        let entry_isdir = *entry_isdir;
+        let entry_path = built_path.join(entry_name);
+
+        if let Some(filter) = path_filter {
+            if !filter(&entry_path) {
+                continue;
+            }
+        }
+
+        if let Some(rules) = &ignore_rules {
+            if ignore_files::is_ignored(rules, &entry_path, entry_isdir) {
+                continue;
+            }
+        }
         let mut built = built.clone();
         built.parts.push(entry_name.clone());
         built.isdir = entry_isdir;
@@ -55,10 +118,21 @@ fn complete_rec(
         if !dir || entry_isdir {
             match partial.split_first() {
                 Some((base, rest)) => {
-                    if matches(base, &entry_name, options) {
+                    let is_final_component = rest.is_empty() && !isdir;
+                    // With `completions.partial` off, an interior component
+                    // (everything but the final one) must resolve exactly,
+                    // rather than fanning out into every directory it
+                    // partially matches.
+                    let is_match = if options.partial || is_final_component {
+                        matches(base, entry_name, options)
+                    } else {
+                        matches_exactly(base, entry_name, options)
+                    };
+                    if is_match {
                         if !rest.is_empty() || isdir {
-                            completions
-                                .extend(complete_rec(rest, &built, cwd, options, dir, isdir));
+                            completions.extend(complete_rec(
+                                rest, &built, cwd, options, dir, isdir, cache, path_filter,
+                            ));
                         } else {
                             completions.push(built);
                         }
@@ -81,7 +155,7 @@ enum OriginalCwd {
 }
 
 impl OriginalCwd {
-    fn apply(&self, mut p: PathBuiltFromString) -> String {
+    fn apply(&self, mut p: PathBuiltFromString, append_slash: bool) -> String {
         match self {
             Self::None => {}
             Self::Home => p.parts.insert(0, "~".to_string()),
@@ -89,27 +163,83 @@ impl OriginalCwd {
         };
 
         let mut ret = p.parts.join(MAIN_SEPARATOR_STR);
-        if p.isdir {
+        if p.isdir && append_slash {
             ret.push(SEP);
         }
         ret
     }
 }
 
-fn surround_remove(partial: &str) -> String {
+/// Distinguishes a plain quote/backtick from a raw-string delimiter. Raw
+/// strings additionally carry the number of `#` characters used to open
+/// them, since `r#'...'#`, `r##'...'##`, etc. are all valid and the closing
+/// delimiter must match.
+#[derive(Clone, Copy)]
+enum OpenDelimiter {
+    Quote(char),
+    RawString(usize),
+}
+
+/// Strips a leading quote/backtick/raw-string opener from `partial` for
+/// matching purposes. Returns the delimiter alongside the stripped text when
+/// it was left unterminated (the user is still typing inside it), so the
+/// completion can be closed with the same delimiter instead of a freshly
+/// chosen one.
+fn surround_remove(partial: &str) -> (String, Option<OpenDelimiter>) {
+    if let Some(rest) = partial.strip_prefix('r') {
+        let sharp_cnt = rest.bytes().take_while(|&b| b == b'#').count();
+        if let Some(inside) = rest[sharp_cnt..].strip_prefix('\'') {
+            return (
+                inside.to_string(),
+                Some(OpenDelimiter::RawString(sharp_cnt)),
+            );
+        }
+    }
+
     for c in ['`', '"', '\''] {
         if partial.starts_with(c) {
             let ret = partial.strip_prefix(c).unwrap_or(partial);
             return match ret.split(c).collect::<Vec<_>>()[..] {
-                [inside] => inside.to_string(),
-                [inside, outside] if inside.ends_with(is_separator) => format!("{inside}{outside}"),
-                _ => ret.to_string(),
+                [inside] => (inside.to_string(), Some(OpenDelimiter::Quote(c))),
+                [inside, outside] if inside.ends_with(is_separator) => {
+                    (format!("{inside}{outside}"), None)
+                }
+                _ => (ret.to_string(), None),
             };
         }
     }
-    partial.to_string()
+    (partial.to_string(), None)
+}
+
+/// Splits the currently-open alternative out of a single, separator-free
+/// path component that contains a glob brace group, e.g. `{test_a,te` splits
+/// into (`{test_a,`, `te`). The prefix -- previously-typed alternatives plus
+/// the `{`/`,` that introduces the current one -- is kept as literal text on
+/// each suggestion, while only the part after it is matched against
+/// directory entries. Returns `None` if `component` has no open (unclosed)
+/// brace group.
+fn brace_alternative_prefix(component: &str) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+    let mut split_at = None;
+
+    for (i, c) in component.char_indices() {
+        match c {
+            '{' => {
+                depth += 1;
+                if depth == 1 {
+                    split_at = Some(i + 1);
+                }
+            }
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 1 => split_at = Some(i + 1),
+            _ => {}
+        }
+    }
+
+    (depth > 0).then(|| component.split_at(split_at.unwrap_or(0)))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn complete_item(
     want_directory: bool,
     span: nu_protocol::Span,
@@ -118,8 +248,33 @@ pub fn complete_item(
     options: &CompletionOptions,
     engine_state: &EngineState,
     stack: &Stack,
+    append_slash: bool,
+    path_filter: Option<&PathFilter>,
 ) -> Vec<(nu_protocol::Span, String, Option<Style>)> {
-    let partial = surround_remove(partial);
+    let (partial, open_quote) = surround_remove(partial);
+
+    // "~user" (as opposed to a bare "~"): complete the username itself,
+    // enumerating matching local accounts, rather than treating `partial` as
+    // a directory to list.
+    if let Some(username_partial) = partial.strip_prefix('~') {
+        if !username_partial.is_empty() && !username_partial.contains(is_separator) {
+            return nu_path::users_with_prefix(username_partial)
+                .into_iter()
+                .map(|name| {
+                    let mut suggestion = format!("~{name}");
+                    if append_slash {
+                        suggestion.push(SEP);
+                    }
+                    (
+                        span,
+                        escape_path_continuing_quote(suggestion, want_directory, open_quote),
+                        None,
+                    )
+                })
+                .collect();
+        }
+    }
+
     let isdir = partial.ends_with(is_separator);
     let cwd_pathbuf = Path::new(cwd).to_path_buf();
     let ls_colors = (engine_state.config.use_ls_colors_completions
@@ -166,13 +321,23 @@ pub fn complete_item(
     };
 
     let after_prefix = &partial[prefix_len..];
-    let partial: Vec<_> = after_prefix
+    let mut partial: Vec<&str> = after_prefix
         .strip_prefix(is_separator)
         .unwrap_or(after_prefix)
         .split(is_separator)
         .filter(|s| !s.is_empty())
         .collect();
 
+    let brace_prefix = if let [component] = partial.as_mut_slice() {
+        brace_alternative_prefix(component).map(|(prefix, member)| {
+            *component = member;
+            prefix
+        })
+    } else {
+        None
+    };
+
+    let mut cache = DirEntryCache::new();
     complete_rec(
         partial.as_slice(),
         &PathBuiltFromString::default(),
@@ -180,10 +345,12 @@ pub fn complete_item(
         options,
         want_directory,
         isdir,
+        &mut cache,
+        path_filter,
     )
     .into_iter()
     .map(|p| {
-        let path = original_cwd.apply(p);
+        let path = original_cwd.apply(p, append_slash);
         let style = ls_colors.as_ref().map(|lsc| {
             lsc.style_for_path_with_metadata(
                 &path,
@@ -194,11 +361,43 @@ pub fn complete_item(
             .map(lscolors::Style::to_nu_ansi_term_style)
             .unwrap_or_default()
         });
-        (span, escape_path(path, want_directory), style)
+        let path = match brace_prefix {
+            Some(prefix) => format!("{prefix}{path}"),
+            None => path,
+        };
+        (
+            span,
+            escape_path_continuing_quote(path, want_directory, open_quote),
+            style,
+        )
     })
     .collect()
 }
 
+/// Like [`escape_path`], but when the user is mid-way through typing an
+/// unterminated quote or raw string (e.g. `open "test di<tab>` or
+/// `open r#'test di<tab>`), closes the completion with that same delimiter
+/// instead of picking a fresh one, as long as doing so doesn't require
+/// escaping something that delimiter can't express (single-quoted strings
+/// and raw strings have no escape mechanism).
+fn escape_path_continuing_quote(
+    path: String,
+    dir: bool,
+    open_quote: Option<OpenDelimiter>,
+) -> String {
+    match open_quote {
+        Some(OpenDelimiter::Quote('"')) => format!("\"{}\"", path.replace('"', "\\\"")),
+        Some(OpenDelimiter::Quote(quote @ ('`' | '\''))) if !path.contains(quote) => {
+            format!("{quote}{path}{quote}")
+        }
+        Some(OpenDelimiter::RawString(sharp_cnt)) if !path.contains('\'') => {
+            let sharps = "#".repeat(sharp_cnt);
+            format!("r{sharps}'{path}'{sharps}")
+        }
+        _ => escape_path(path, dir),
+    }
+}
+
 // Fix files or folders with quotes or hashes
 pub fn escape_path(path: String, dir: bool) -> String {
     // make glob pattern have the highest priority.
@@ -230,6 +429,17 @@ pub struct AdjustView {
     pub readjusted: bool,
 }
 
+/// Handles completion when the cursor sits in the middle of an existing
+/// token (e.g. `cp fo|o.txt` with the cursor at `|`), rather than at its end.
+/// Assumes `span` covers `prefix`, followed by a single placeholder character
+/// standing in for the cursor, followed by the rest of the token as already
+/// typed -- the convention callers use to make an otherwise-unparseable
+/// mid-token buffer parse. `span` always stays a byte range into that buffer;
+/// callers that build a [`reedline::Suggestion`] from it (see
+/// [`super::FileCompletion`] and [`super::DirectoryCompletion`]) forward it
+/// more or less as-is, so any char-counted arithmetic here would leak into
+/// the replacement span reedline uses, corrupting it whenever a multibyte
+/// character appears before the cursor.
 pub fn adjust_if_intermediate(
     prefix: &[u8],
     working_set: &StateWorkingSet,
@@ -242,13 +452,21 @@ pub fn adjust_if_intermediate(
     // Using .chars().count() because unicode and Windows.
     let readjusted = span_contents.chars().count() - prefix.chars().count() > 1;
     if readjusted {
-        let remnant: String = span_contents
+        // `span` is a byte range, so the remnant's start (and end) must be
+        // located by byte offset, not char count -- otherwise a multibyte
+        // character anywhere before the cursor shifts every completion span
+        // after it out of alignment with the actual token bytes.
+        let remnant_start = span_contents
+            .char_indices()
+            .nth(prefix.chars().count() + 1)
+            .map_or(span_contents.len(), |(i, _)| i);
+        let remnant: String = span_contents[remnant_start..]
             .chars()
-            .skip(prefix.chars().count() + 1)
             .take_while(|&c| !is_separator(c))
             .collect();
+        let consumed_end = remnant_start + remnant.len();
         prefix.push_str(&remnant);
-        span = Span::new(span.start, span.start + prefix.chars().count() + 1);
+        span = Span::new(span.start, span.start + consumed_end);
     }
     AdjustView {
         prefix,
@@ -256,3 +474,37 @@ pub fn adjust_if_intermediate(
         readjusted,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nu_protocol::engine::EngineState;
+
+    // Mirrors the convention `adjust_if_intermediate`'s callers rely on: the
+    // buffer they hand it has a single placeholder character sitting at the
+    // cursor, in between the already-typed prefix and whatever the user typed
+    // after the cursor.
+    #[test]
+    fn readjusts_using_byte_offsets_around_a_multibyte_prefix() {
+        let engine_state = EngineState::new();
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        // "café_fo" (7 chars, 8 bytes because of the 2-byte 'é') + a
+        // placeholder cursor char + the rest of the real token, "lder".
+        let contents = "café_fozlder";
+        let start = working_set.next_span_start();
+        working_set.add_file("test".into(), contents.as_bytes());
+        let span = Span::new(start, start + contents.len());
+
+        let view = adjust_if_intermediate("café_fo".as_bytes(), &working_set, span);
+
+        assert!(view.readjusted);
+        assert_eq!(view.prefix, "café_folder");
+        // The byte range must line up with the real bytes of "café_fo" +
+        // placeholder + "lder", not a char-counted approximation that comes
+        // up short once the 2-byte 'é' is involved.
+        assert_eq!(
+            working_set.get_span_contents(view.span),
+            contents.as_bytes()
+        );
+    }
+}
@@ -1,7 +1,19 @@
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use nu_parser::trim_quotes_str;
-use nu_protocol::CompletionAlgorithm;
+use nu_protocol::{CompletionAlgorithm, CompletionSort, FuzzyAnchor};
+use nu_utils::IgnoreCaseExt;
 use std::fmt::Display;
+use unicode_normalization::UnicodeNormalization;
+
+/// Decomposes `text` (NFKD) and drops the resulting combining diacritical
+/// marks, so accented characters fuzzy-match their unaccented form, e.g.
+/// `"cafe"` matching `"café"`. Only used to decide/score a match -- the
+/// suggestion's own value and byte spans are untouched.
+fn strip_diacritics(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .collect()
+}
 
 #[derive(Copy, Clone)]
 pub enum SortBy {
@@ -19,11 +31,18 @@ pub enum MatchAlgorithm {
     /// "git switch" is matched by "git sw"
     Prefix,
 
-    /// Only show suggestions which contain the input chars at any place
+    /// Only show suggestions which contain the input chars at any place,
+    /// per the anchoring rule the [`FuzzyAnchor`] carries
     ///
     /// Example:
     /// "git checkout" is matched by "gco"
-    Fuzzy,
+    Fuzzy(FuzzyAnchor),
+
+    /// Only show suggestions which contain the input as a contiguous, case-insensitive substring
+    ///
+    /// Example:
+    /// "git checkout" is matched by "checko" but not by "gco"
+    Substring,
 }
 
 impl MatchAlgorithm {
@@ -33,10 +52,28 @@ impl MatchAlgorithm {
         let needle = trim_quotes_str(needle);
         match *self {
             MatchAlgorithm::Prefix => haystack.starts_with(needle),
-            MatchAlgorithm::Fuzzy => {
-                let matcher = SkimMatcherV2::default();
-                matcher.fuzzy_match(haystack, needle).is_some()
+            MatchAlgorithm::Fuzzy(anchor) => {
+                fuzzy_indices(&strip_diacritics(haystack), &strip_diacritics(needle), anchor)
+                    .is_some()
+            }
+            MatchAlgorithm::Substring => {
+                haystack.to_folded_case().contains(&needle.to_folded_case())
+            }
+        }
+    }
+
+    /// Returns the fuzzy match score of `needle` against `haystack`, or `None` if the
+    /// algorithm isn't [`MatchAlgorithm::Fuzzy`] or the strings don't match at all.
+    /// Higher scores indicate a better match.
+    pub fn fuzzy_score(&self, haystack: &str, needle: &str) -> Option<f64> {
+        match *self {
+            MatchAlgorithm::Fuzzy(anchor) => {
+                let haystack = trim_quotes_str(haystack);
+                let needle = trim_quotes_str(needle);
+                fuzzy_indices(&strip_diacritics(haystack), &strip_diacritics(needle), anchor)
+                    .map(|(score, _)| score as f64)
             }
+            MatchAlgorithm::Prefix | MatchAlgorithm::Substring => None,
         }
     }
 
@@ -44,22 +81,43 @@ impl MatchAlgorithm {
     pub fn matches_u8(&self, haystack: &[u8], needle: &[u8]) -> bool {
         match *self {
             MatchAlgorithm::Prefix => haystack.starts_with(needle),
-            MatchAlgorithm::Fuzzy => {
-                let haystack_str = String::from_utf8_lossy(haystack);
-                let needle_str = String::from_utf8_lossy(needle);
+            MatchAlgorithm::Fuzzy(anchor) => {
+                let haystack_str = strip_diacritics(&String::from_utf8_lossy(haystack));
+                let needle_str = strip_diacritics(&String::from_utf8_lossy(needle));
+
+                fuzzy_indices(&haystack_str, &needle_str, anchor).is_some()
+            }
+            MatchAlgorithm::Substring => {
+                let haystack_str = String::from_utf8_lossy(haystack).to_folded_case();
+                let needle_str = String::from_utf8_lossy(needle).to_folded_case();
 
-                let matcher = SkimMatcherV2::default();
-                matcher.fuzzy_match(&haystack_str, &needle_str).is_some()
+                haystack_str.contains(&needle_str)
             }
         }
     }
-}
 
-impl From<CompletionAlgorithm> for MatchAlgorithm {
-    fn from(value: CompletionAlgorithm) -> Self {
-        match value {
+    /// Builds a [`MatchAlgorithm`] from `$env.config.completions.algorithm`
+    /// and (for `"fuzzy"`) `completions.fuzzy_anchor`.
+    pub fn from_config(algorithm: CompletionAlgorithm, fuzzy_anchor: FuzzyAnchor) -> Self {
+        match algorithm {
             CompletionAlgorithm::Prefix => MatchAlgorithm::Prefix,
-            CompletionAlgorithm::Fuzzy => MatchAlgorithm::Fuzzy,
+            CompletionAlgorithm::Fuzzy => MatchAlgorithm::Fuzzy(fuzzy_anchor),
+            CompletionAlgorithm::Substring => MatchAlgorithm::Substring,
+        }
+    }
+}
+
+/// Runs the skim fuzzy matcher and, for [`FuzzyAnchor::Start`], additionally
+/// requires the match's first matched character be `haystack`'s first
+/// character -- otherwise a needle like "cd" would happily match "src-cd"
+/// even though the user is anchoring to the start of the word.
+fn fuzzy_indices(haystack: &str, needle: &str, anchor: FuzzyAnchor) -> Option<(i64, Vec<usize>)> {
+    let matcher = SkimMatcherV2::default();
+    let (score, indices) = matcher.fuzzy_indices(haystack, needle)?;
+    match anchor {
+        FuzzyAnchor::Anywhere => Some((score, indices)),
+        FuzzyAnchor::Start => {
+            (needle.is_empty() || indices.first() == Some(&0)).then_some((score, indices))
         }
     }
 }
@@ -70,7 +128,8 @@ impl TryFrom<String> for MatchAlgorithm {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         match value.as_str() {
             "prefix" => Ok(Self::Prefix),
-            "fuzzy" => Ok(Self::Fuzzy),
+            "fuzzy" => Ok(Self::Fuzzy(FuzzyAnchor::default())),
+            "substring" => Ok(Self::Substring),
             _ => Err(InvalidMatchAlgorithm::Unknown),
         }
     }
@@ -96,6 +155,23 @@ pub struct CompletionOptions {
     pub case_sensitive: bool,
     pub positional: bool,
     pub match_algorithm: MatchAlgorithm,
+    /// Whether file completions should include entries whose name starts with a dot.
+    pub hidden_files: bool,
+    /// Whether directory completions should suggest a trailing path separator.
+    pub append_slash: bool,
+    /// Whether file completions should be filtered using the nearest
+    /// `.gitignore`/`.ignore` rules.
+    pub use_ignore_files: bool,
+    /// Whether file/path completions should group directory suggestions
+    /// ahead of file suggestions, per `completions.dirs_first`.
+    pub dirs_first: bool,
+    /// How suggestions should be ordered, per `completions.sort`.
+    pub sort: CompletionSort,
+    /// Whether a partially-typed directory component (e.g. `pa` in `pa/h`)
+    /// can expand into every directory it matches, per `completions.partial`.
+    /// When false, only the final path component is matched this way --
+    /// earlier components must resolve to an existing directory exactly.
+    pub partial: bool,
 }
 
 impl Default for CompletionOptions {
@@ -104,13 +180,33 @@ impl Default for CompletionOptions {
             case_sensitive: true,
             positional: true,
             match_algorithm: MatchAlgorithm::Prefix,
+            hidden_files: true,
+            append_slash: true,
+            use_ignore_files: false,
+            dirs_first: false,
+            sort: CompletionSort::default(),
+            partial: true,
+        }
+    }
+}
+
+impl CompletionOptions {
+    /// Returns a copy of these options with `case_sensitive` overridden for
+    /// file/path completion, per `completions.case_sensitive_paths`. `None`
+    /// leaves `case_sensitive` as-is (i.e. follows `completions.case_sensitive`).
+    pub fn with_case_sensitive_paths(&self, case_sensitive_paths: Option<bool>) -> Self {
+        let mut options = self.clone();
+        if let Some(case_sensitive) = case_sensitive_paths {
+            options.case_sensitive = case_sensitive;
         }
+        options
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::MatchAlgorithm;
+    use nu_protocol::FuzzyAnchor;
 
     #[test]
     fn match_algorithm_prefix() {
@@ -127,7 +223,7 @@ mod test {
 
     #[test]
     fn match_algorithm_fuzzy() {
-        let algorithm = MatchAlgorithm::Fuzzy;
+        let algorithm = MatchAlgorithm::Fuzzy(FuzzyAnchor::Anywhere);
 
         assert!(algorithm.matches_str("example text", ""));
         assert!(algorithm.matches_str("example text", "examp"));
@@ -141,4 +237,45 @@ mod test {
         assert!(algorithm.matches_u8(&[1, 2, 3], &[1, 3]));
         assert!(!algorithm.matches_u8(&[1, 2, 3], &[2, 2]));
     }
+
+    #[test]
+    fn match_algorithm_fuzzy_ignores_accents() {
+        let algorithm = MatchAlgorithm::Fuzzy(FuzzyAnchor::Anywhere);
+
+        assert!(algorithm.matches_str("café", "cafe"));
+        assert!(algorithm.matches_u8("café".as_bytes(), b"cafe"));
+        assert!(algorithm.fuzzy_score("café", "cafe").is_some());
+    }
+
+    #[test]
+    fn match_algorithm_fuzzy_start_requires_first_char_to_match() {
+        let algorithm = MatchAlgorithm::Fuzzy(FuzzyAnchor::Start);
+
+        assert!(algorithm.matches_str("cd-project", "cd"));
+        assert!(!algorithm.matches_str("src-cd", "cd"));
+
+        // An empty needle still matches everything, same as `Anywhere`.
+        assert!(algorithm.matches_str("cd-project", ""));
+    }
+
+    #[test]
+    fn match_algorithm_substring() {
+        let algorithm = MatchAlgorithm::Substring;
+
+        assert!(algorithm.matches_str("example text", ""));
+        assert!(algorithm.matches_str("example text", "text"));
+        assert!(algorithm.matches_str("example text", "EXAMPLE"));
+        assert!(!algorithm.matches_str("example text", "mplxt"));
+
+        let haystack = ["Foo Abcdef", "Abcdef", "Acd Bar"];
+        let matches: Vec<&str> = haystack
+            .into_iter()
+            .filter(|h| algorithm.matches_str(h, "Abcd"))
+            .collect();
+        assert_eq!(matches, vec!["Foo Abcdef", "Abcdef"]);
+
+        assert!(algorithm.matches_u8(&[1, 2, 3], &[]));
+        assert!(algorithm.matches_u8(&[1, 2, 3], &[2, 3]));
+        assert!(!algorithm.matches_u8(&[1, 2, 3], &[1, 3]));
+    }
 }
@@ -0,0 +1,67 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span,
+};
+use reedline::Suggestion;
+
+/// Completes the right-hand side of `$env.config.<path> = <tab>` for the
+/// handful of config fields whose valid values are a known, closed set
+/// (e.g. `completions.algorithm`). Unknown paths yield no suggestions.
+#[derive(Clone)]
+pub struct ConfigValueCompletion {
+    path: Vec<Vec<u8>>,
+}
+
+impl ConfigValueCompletion {
+    pub fn new(path: Vec<Vec<u8>>) -> Self {
+        Self { path }
+    }
+}
+
+impl Completer for ConfigValueCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let path: Vec<&[u8]> = self.path.iter().map(Vec::as_slice).collect();
+        let candidates: &[&str] = match path.as_slice() {
+            [b"completions", b"algorithm"] => &["prefix", "fuzzy", "substring"],
+            [b"completions", b"sort"] => &["alphabetical", "smart"],
+            _ => &[],
+        };
+
+        candidates
+            .iter()
+            .filter(|candidate| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    candidate.as_bytes(),
+                    &prefix,
+                )
+            })
+            .map(|candidate| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: candidate.to_string(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: None,
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
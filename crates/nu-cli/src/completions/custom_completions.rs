@@ -1,13 +1,13 @@
 use crate::completions::{
-    completer::map_value_completions, Completer, CompletionOptions, MatchAlgorithm,
-    SemanticSuggestion, SortBy,
+    base::prefer_exact_case_matches, completer::map_value_completions, Completer,
+    CompletionOptions, MatchAlgorithm, SemanticSuggestion, SortBy,
 };
 use nu_engine::eval_call;
 use nu_protocol::{
     ast::{Argument, Call, Expr, Expression},
     debugger::WithoutDebug,
     engine::{Stack, StateWorkingSet},
-    PipelineData, Span, Type, Value,
+    levenshtein_distance, PipelineData, Span, Type, Value,
 };
 use nu_utils::IgnoreCaseExt;
 use std::collections::HashMap;
@@ -17,6 +17,9 @@ pub struct CustomCompletion {
     decl_id: usize,
     line: String,
     sort_by: SortBy,
+    /// Caps the number of suggestions returned, set via `options.max_results`
+    /// on the completer's result record. Applied after sorting.
+    max_results: Option<i64>,
 }
 
 impl CustomCompletion {
@@ -26,6 +29,7 @@ impl CustomCompletion {
             decl_id,
             line,
             sort_by: SortBy::None,
+            max_results: None,
         }
     }
 }
@@ -69,6 +73,7 @@ impl Completer for CustomCompletion {
         );
 
         let mut custom_completion_options = None;
+        let mut common_prefix = None;
 
         // Parse result
         let suggestions = result
@@ -95,6 +100,12 @@ impl Completer for CustomCompletion {
                             self.sort_by = SortBy::Ascending;
                         }
 
+                        self.max_results =
+                            options.get("max_results").and_then(|val| val.as_int().ok());
+
+                        common_prefix =
+                            options.get("prefix").and_then(|val| val.coerce_string().ok());
+
                         custom_completion_options = Some(CompletionOptions {
                             case_sensitive: options
                                 .get("case_sensitive")
@@ -112,6 +123,12 @@ impl Completer for CustomCompletion {
                                     .unwrap_or(MatchAlgorithm::Prefix),
                                 None => completion_options.match_algorithm,
                             },
+                            hidden_files: completion_options.hidden_files,
+                            append_slash: completion_options.append_slash,
+                            use_ignore_files: completion_options.use_ignore_files,
+                            dirs_first: completion_options.dirs_first,
+                            sort: completion_options.sort,
+                            partial: completion_options.partial,
                         });
                     }
 
@@ -122,16 +139,70 @@ impl Completer for CustomCompletion {
             })
             .unwrap_or_default();
 
-        if let Some(custom_completion_options) = custom_completion_options {
+        let suggestions = if let Some(custom_completion_options) = custom_completion_options {
             filter(&prefix, suggestions, &custom_completion_options)
         } else {
             filter(&prefix, suggestions, completion_options)
+        };
+
+        match common_prefix {
+            Some(common_prefix) => strip_common_prefix(suggestions, common_prefix.as_bytes()),
+            None => suggestions,
         }
     }
 
     fn get_sort_by(&self) -> SortBy {
         self.sort_by
     }
+
+    fn sort(&self, items: Vec<SemanticSuggestion>, prefix: Vec<u8>) -> Vec<SemanticSuggestion> {
+        let prefix_str = String::from_utf8_lossy(&prefix).to_string();
+        let mut sorted_items = items;
+
+        match self.get_sort_by() {
+            SortBy::LevenshteinDistance => {
+                sorted_items.sort_by(|a, b| {
+                    let a_distance = levenshtein_distance(&prefix_str, &a.suggestion.value);
+                    let b_distance = levenshtein_distance(&prefix_str, &b.suggestion.value);
+                    a_distance.cmp(&b_distance)
+                });
+            }
+            SortBy::Ascending => {
+                sorted_items.sort_by(|a, b| a.suggestion.value.cmp(&b.suggestion.value));
+            }
+            SortBy::None => {}
+        }
+
+        prefer_exact_case_matches(&mut sorted_items, &prefix);
+
+        if let Some(max_results) = self.max_results.and_then(|max| usize::try_from(max).ok()) {
+            sorted_items.truncate(max_results);
+        }
+
+        sorted_items
+    }
+}
+
+/// Strips `options.prefix` from each suggestion's value and narrows its
+/// replacement span to match, so a completer can return values that include
+/// a shared prefix the user already typed (e.g. `feat/x` when every branch
+/// starts with `feat/`) without nushell re-inserting text that's already in
+/// the buffer.
+fn strip_common_prefix(
+    suggestions: Vec<SemanticSuggestion>,
+    common_prefix: &[u8],
+) -> Vec<SemanticSuggestion> {
+    suggestions
+        .into_iter()
+        .map(|mut suggestion| {
+            if suggestion.suggestion.value.as_bytes().starts_with(common_prefix) {
+                suggestion.suggestion.value =
+                    suggestion.suggestion.value[common_prefix.len()..].to_string();
+                suggestion.suggestion.span.start += common_prefix.len();
+            }
+            suggestion
+        })
+        .collect()
 }
 
 fn filter(
@@ -158,7 +229,7 @@ fn filter(
                     }
                 }
             },
-            MatchAlgorithm::Fuzzy => options
+            MatchAlgorithm::Fuzzy(_) | MatchAlgorithm::Substring => options
                 .match_algorithm
                 .matches_u8(it.suggestion.value.as_bytes(), prefix),
         })
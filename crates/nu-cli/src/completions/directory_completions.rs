@@ -33,6 +33,12 @@ impl Completer for DirectoryCompletion {
         options: &CompletionOptions,
     ) -> Vec<SemanticSuggestion> {
         let AdjustView { prefix, span, .. } = adjust_if_intermediate(&prefix, working_set, span);
+        let options = &options.with_case_sensitive_paths(
+            working_set
+                .permanent_state
+                .get_config()
+                .completions_case_sensitive_paths,
+        );
 
         // Filter only the folders
         #[allow(deprecated)]
@@ -59,6 +65,8 @@ impl Completer for DirectoryCompletion {
             },
             // TODO????
             kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
         })
         .collect();
 
@@ -125,5 +133,15 @@ pub fn directory_completion(
     engine_state: &EngineState,
     stack: &Stack,
 ) -> Vec<(nu_protocol::Span, String, Option<Style>)> {
-    complete_item(true, span, partial, cwd, options, engine_state, stack)
+    complete_item(
+        true,
+        span,
+        partial,
+        cwd,
+        options,
+        engine_state,
+        stack,
+        options.append_slash,
+        None,
+    )
 }
@@ -4,7 +4,10 @@ use nu_protocol::{
     Span,
 };
 use reedline::Suggestion;
-use std::path::{is_separator, Path, MAIN_SEPARATOR as SEP, MAIN_SEPARATOR_STR};
+use std::{
+    collections::HashSet,
+    path::{is_separator, Path, MAIN_SEPARATOR as SEP, MAIN_SEPARATOR_STR},
+};
 
 use super::SemanticSuggestion;
 
@@ -28,6 +31,12 @@ impl Completer for DotNuCompletion {
         _pos: usize,
         options: &CompletionOptions,
     ) -> Vec<SemanticSuggestion> {
+        let options = &options.with_case_sensitive_paths(
+            working_set
+                .permanent_state
+                .get_config()
+                .completions_case_sensitive_paths,
+        );
         let prefix_str = String::from_utf8_lossy(&prefix).replace('`', "");
         let mut search_dirs: Vec<String> = vec![];
 
@@ -87,6 +96,10 @@ impl Completer for DotNuCompletion {
 
         // Fetch the files filtering the ones that ends with .nu
         // and transform them into suggestions
+        // The same file can live under more than one search dir (e.g. a lib dir
+        // and the current directory), so only keep the first suggestion we see
+        // for a given value.
+        let mut seen = HashSet::new();
         let output: Vec<SemanticSuggestion> = search_dirs
             .into_iter()
             .flat_map(|search_dir| {
@@ -127,8 +140,11 @@ impl Completer for DotNuCompletion {
                         },
                         // TODO????
                         kind: None,
+                        match_score: None,
+                        is_unambiguous_match: false,
                     })
             })
+            .filter(move |it| seen.insert(it.suggestion.value.clone()))
             .collect();
 
         output
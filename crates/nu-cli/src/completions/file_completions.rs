@@ -1,5 +1,5 @@
 use crate::completions::{
-    completion_common::{adjust_if_intermediate, complete_item, AdjustView},
+    completion_common::{adjust_if_intermediate, complete_item, AdjustView, PathFilter},
     Completer, CompletionOptions, SortBy,
 };
 use nu_ansi_term::Style;
@@ -14,12 +14,38 @@ use std::path::{Path, MAIN_SEPARATOR as SEP};
 use super::SemanticSuggestion;
 
 #[derive(Clone, Default)]
-pub struct FileCompletion {}
+pub struct FileCompletion {
+    /// Overrides the working directory completions are resolved relative to,
+    /// e.g. a command mapped to a base directory via
+    /// `NuCompleter::register_path_root` or `completions.path_roots`. `None`
+    /// falls back to the session's actual current working directory.
+    base_dir: Option<String>,
+    /// Set from `completions.dirs_first` at the start of [`Self::fetch`], so
+    /// [`Self::sort`] (which doesn't receive `CompletionOptions`) can still
+    /// see it.
+    dirs_first: bool,
+    /// Set from `NuCompleter::set_path_filter`. Entries the predicate
+    /// rejects are dropped, and traversal doesn't descend into a rejected
+    /// directory.
+    path_filter: Option<PathFilter>,
+}
 
 impl FileCompletion {
     pub fn new() -> Self {
         Self::default()
     }
+
+    pub fn with_base_dir(base_dir: String) -> Self {
+        Self {
+            base_dir: Some(base_dir),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_path_filter(mut self, path_filter: Option<PathFilter>) -> Self {
+        self.path_filter = path_filter;
+        self
+    }
 }
 
 impl Completer for FileCompletion {
@@ -33,21 +59,36 @@ impl Completer for FileCompletion {
         _pos: usize,
         options: &CompletionOptions,
     ) -> Vec<SemanticSuggestion> {
+        self.dirs_first = options.dirs_first;
+
         let AdjustView {
             prefix,
             span,
             readjusted,
         } = adjust_if_intermediate(&prefix, working_set, span);
+        let options = &options.with_case_sensitive_paths(
+            working_set
+                .permanent_state
+                .get_config()
+                .completions_case_sensitive_paths,
+        );
+
+        let cwd = self
+            .base_dir
+            .clone()
+            .unwrap_or_else(|| working_set.permanent_state.current_work_dir());
 
         #[allow(deprecated)]
         let output: Vec<_> = complete_item(
             readjusted,
             span,
             &prefix,
-            &working_set.permanent_state.current_work_dir(),
+            &cwd,
             options,
             working_set.permanent_state,
             stack,
+            true,
+            self.path_filter.as_ref(),
         )
         .into_iter()
         .map(move |x| SemanticSuggestion {
@@ -64,6 +105,8 @@ impl Completer for FileCompletion {
             },
             // TODO????
             kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
         })
         .collect();
 
@@ -115,6 +158,10 @@ impl Completer for FileCompletion {
             }
         }
 
+        if self.dirs_first {
+            non_hidden = group_dirs_first(non_hidden);
+        }
+
         // Append the hidden folders to the non hidden vec to avoid creating a new vec
         non_hidden.append(&mut hidden);
 
@@ -122,6 +169,18 @@ impl Completer for FileCompletion {
     }
 }
 
+/// Stably partitions `items` so directory suggestions (kept trailing-slashed
+/// by [`file_path_completion`]) come before file suggestions, preserving
+/// each group's existing relative order.
+fn group_dirs_first(items: Vec<SemanticSuggestion>) -> Vec<SemanticSuggestion> {
+    let (mut dirs, files): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .partition(|item| item.suggestion.value.ends_with(SEP));
+
+    dirs.extend(files);
+    dirs
+}
+
 pub fn file_path_completion(
     span: nu_protocol::Span,
     partial: &str,
@@ -130,7 +189,19 @@ pub fn file_path_completion(
     engine_state: &EngineState,
     stack: &Stack,
 ) -> Vec<(nu_protocol::Span, String, Option<Style>)> {
-    complete_item(false, span, partial, cwd, options, engine_state, stack)
+    // File, glob and dot-nu completions always keep the trailing separator on
+    // directory entries; `append_slash` only affects `DirectoryCompletion`.
+    complete_item(
+        false,
+        span,
+        partial,
+        cwd,
+        options,
+        engine_state,
+        stack,
+        true,
+        None,
+    )
 }
 
 pub fn matches(partial: &str, from: &str, options: &CompletionOptions) -> bool {
@@ -143,3 +214,17 @@ pub fn matches(partial: &str, from: &str, options: &CompletionOptions) -> bool {
 
     options.match_algorithm.matches_str(from, partial)
 }
+
+/// Like [`matches`], but requires `from` to equal `partial` exactly (subject
+/// to [`CompletionOptions::case_sensitive`]) instead of applying the
+/// configured match algorithm. Used for interior path components when
+/// `completions.partial` is disabled, so a partially-typed directory name
+/// can't fan out into every directory it matches -- only the final
+/// component still does that.
+pub fn matches_exactly(partial: &str, from: &str, options: &CompletionOptions) -> bool {
+    if !options.case_sensitive {
+        return from.to_folded_case() == partial.to_folded_case();
+    }
+
+    from == partial
+}
@@ -2,7 +2,7 @@ use crate::completions::{Completer, CompletionOptions};
 use nu_protocol::{
     ast::{Expr, Expression},
     engine::{Stack, StateWorkingSet},
-    Span,
+    Flag, Span,
 };
 use reedline::Suggestion;
 
@@ -40,15 +40,23 @@ impl Completer for FlagCompletion {
             for named in &sig.named {
                 let flag_desc = &named.desc;
                 if let Some(short) = named.short {
-                    let mut named = vec![0; short.len_utf8()];
-                    short.encode_utf8(&mut named);
-                    named.insert(0, b'-');
+                    let mut short_named = vec![0; short.len_utf8()];
+                    short.encode_utf8(&mut short_named);
+                    short_named.insert(0, b'-');
 
-                    if options.match_algorithm.matches_u8(&named, &prefix) {
+                    // Mention the long-form equivalent in the description, so
+                    // e.g. `-l` shows up described as `--long`.
+                    let description = if named.long.is_empty() {
+                        flag_desc.to_string()
+                    } else {
+                        format!("{flag_desc} (--{})", named.long)
+                    };
+
+                    if options.match_algorithm.matches_u8(&short_named, &prefix) {
                         output.push(SemanticSuggestion {
                             suggestion: Suggestion {
-                                value: String::from_utf8_lossy(&named).to_string(),
-                                description: Some(flag_desc.to_string()),
+                                value: String::from_utf8_lossy(&short_named).to_string(),
+                                description: Some(description),
                                 style: None,
                                 extra: None,
                                 span: reedline::Span {
@@ -59,6 +67,8 @@ impl Completer for FlagCompletion {
                             },
                             // TODO????
                             kind: None,
+                            match_score: None,
+                            is_unambiguous_match: false,
                         });
                     }
                 }
@@ -86,13 +96,82 @@ impl Completer for FlagCompletion {
                         },
                         // TODO????
                         kind: None,
+                        match_score: None,
+                        is_unambiguous_match: false,
                     });
                 }
             }
 
+            output.extend(cluster_continuations(&sig.named, &prefix, span, offset));
+
             return output;
         }
 
         vec![]
     }
 }
+
+/// Suggests appending another single-char switch to an already-typed short
+/// flag cluster, e.g. completing `-a` to `-al`, `-ad`, ... instead of only
+/// ever restarting from a bare `-`. Only switches (flags that take no
+/// argument) are clusterable, matching the getopt convention `ls -al`
+/// relies on.
+fn cluster_continuations(
+    named: &[Flag],
+    prefix: &[u8],
+    span: Span,
+    offset: usize,
+) -> Vec<SemanticSuggestion> {
+    // A cluster looks like `-al`: a single leading dash (not `--`) followed
+    // by at least one already-typed short flag.
+    if prefix.len() < 2 || prefix[0] != b'-' || prefix[1] == b'-' {
+        return vec![];
+    }
+
+    let Ok(cluster) = std::str::from_utf8(prefix) else {
+        return vec![];
+    };
+    let typed: Vec<char> = cluster[1..].chars().collect();
+
+    let switches: Vec<&Flag> = named.iter().filter(|f| f.arg.is_none()).collect();
+
+    // Every already-typed char must itself be a known switch, or this isn't
+    // really a cluster (e.g. it's a flag name being typed out that just
+    // happens to start with a switch's letter).
+    if !typed
+        .iter()
+        .all(|c| switches.iter().any(|f| f.short == Some(*c)))
+    {
+        return vec![];
+    }
+
+    switches
+        .into_iter()
+        .filter(|f| f.short.is_some_and(|c| !typed.contains(&c)))
+        .map(|f| {
+            let short = f.short.expect("filtered to switches with a short form");
+            let description = if f.long.is_empty() {
+                f.desc.clone()
+            } else {
+                format!("{} (--{})", f.desc, f.long)
+            };
+
+            SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: format!("{cluster}{short}"),
+                    description: Some(description),
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: None,
+                match_score: None,
+                is_unambiguous_match: false,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SuggestionKind};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span,
+};
+use reedline::Suggestion;
+
+/// Subtopics `help` accepts as its first word besides a command name, e.g.
+/// `help commands` lists every command instead of looking for one literally
+/// named `commands`.
+const HELP_SUBTOPICS: &[&str] = &[
+    "commands",
+    "modules",
+    "aliases",
+    "externs",
+    "operators",
+    "escapes",
+];
+
+/// Completes the first word of `help <tab>` with command names (so `help ls`
+/// suggests `ls`) plus the built-in subtopics above (so `help mod` suggests
+/// `modules`).
+#[derive(Clone)]
+pub struct HelpCompletion;
+
+impl HelpCompletion {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Completer for HelpCompletion {
+    fn fetch(
+        &mut self,
+        working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let filter_predicate =
+            |command_name: &[u8], _: &dyn nu_protocol::engine::Command| {
+                options.match_algorithm.matches_u8(command_name, &prefix)
+            };
+
+        let mut suggestions: Vec<SemanticSuggestion> = working_set
+            .find_commands_by_predicate(filter_predicate, true)
+            .into_iter()
+            .map(|x| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: String::from_utf8_lossy(&x.0).to_string(),
+                    description: x.1,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span::new(span.start - offset, span.end - offset),
+                    append_whitespace: true,
+                },
+                kind: Some(SuggestionKind::Command(x.2)),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect();
+
+        suggestions.extend(
+            HELP_SUBTOPICS
+                .iter()
+                .filter(|topic| {
+                    options.match_algorithm.matches_u8_insensitive(
+                        options.case_sensitive,
+                        topic.as_bytes(),
+                        &prefix,
+                    )
+                })
+                .map(|topic| SemanticSuggestion {
+                    suggestion: Suggestion {
+                        value: topic.to_string(),
+                        description: None,
+                        style: None,
+                        extra: None,
+                        span: reedline::Span::new(span.start - offset, span.end - offset),
+                        append_whitespace: true,
+                    },
+                    kind: None,
+                    match_score: None,
+                    is_unambiguous_match: false,
+                }),
+        );
+
+        suggestions
+    }
+}
@@ -0,0 +1,195 @@
+//! A small, self-contained subset of `.gitignore`/`.ignore` matching, used by
+//! [`FileCompletion`](super::FileCompletion) when
+//! `$env.config.completions.use_ignore_files` is set. This intentionally
+//! doesn't aim for full git compatibility (no `**` corner cases, no
+//! `.gitignore`-in-a-parent-repo boundary detection) -- just enough to hide
+//! common build-artifact directories like `target/` or `node_modules/` from
+//! file completions.
+
+use std::path::{Path, PathBuf};
+
+/// A single parsed line from a `.gitignore`/`.ignore` file.
+pub(crate) struct IgnoreRule {
+    /// The directory the ignore file lives in. Anchored patterns (containing
+    /// a `/`, or written with a leading `/`) are matched relative to this.
+    root: PathBuf,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// Collects ignore rules from `dir` and every ancestor directory, farthest
+/// first, so that rules from nearer directories come later and override
+/// them, mirroring how git layers `.gitignore` files down a tree.
+pub fn collect_ignore_rules(dir: &Path) -> Vec<IgnoreRule> {
+    let mut rules = Vec::new();
+    let mut current = Some(dir.to_path_buf());
+
+    while let Some(d) = current {
+        let mut dir_rules = Vec::new();
+        for file_name in [".gitignore", ".ignore"] {
+            if let Ok(contents) = std::fs::read_to_string(d.join(file_name)) {
+                dir_rules.extend(
+                    contents
+                        .lines()
+                        .filter_map(|line| parse_ignore_line(&d, line)),
+                );
+            }
+        }
+        // `d` is farther from `dir` than every directory visited so far.
+        rules.splice(0..0, dir_rules);
+        current = d.parent().map(Path::to_path_buf);
+    }
+
+    rules
+}
+
+fn parse_ignore_line(root: &Path, line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (leading_slash, line) = match line.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(IgnoreRule {
+        root: root.to_path_buf(),
+        anchored: leading_slash || line.contains('/'),
+        pattern: line.to_string(),
+        negated,
+        dir_only,
+    })
+}
+
+/// Returns whether `full_path` is ignored by `rules`. Later (nearer)
+/// rules override earlier ones, and a `!`-prefixed rule can re-include a
+/// path an earlier rule excluded, matching git's own precedence.
+pub fn is_ignored(rules: &[IgnoreRule], full_path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+
+    for rule in rules {
+        if rule.dir_only && !is_dir {
+            continue;
+        }
+
+        let candidate = if rule.anchored {
+            full_path
+                .strip_prefix(&rule.root)
+                .ok()
+                .map(|p| p.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/"))
+        } else {
+            full_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        };
+
+        let Some(candidate) = candidate else {
+            continue;
+        };
+
+        if glob_match(&rule.pattern, &candidate) {
+            ignored = !rule.negated;
+        }
+    }
+
+    ignored
+}
+
+/// Matches a `.gitignore`-style glob (`*`, `**`, `?`) against `candidate`.
+fn glob_match(pattern: &str, candidate: &str) -> bool {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '{' | '}' | '[' | ']' | '\\' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            _ => regex.push(c),
+        }
+    }
+    regex.push('$');
+
+    fancy_regex::Regex::new(&regex)
+        .map(|re| re.is_match(candidate).unwrap_or(false))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_simple_directory_pattern() {
+        let rules = vec![IgnoreRule {
+            root: PathBuf::from("/repo"),
+            pattern: "target".to_string(),
+            negated: false,
+            dir_only: true,
+            anchored: false,
+        }];
+
+        assert!(is_ignored(&rules, Path::new("/repo/target"), true));
+        assert!(!is_ignored(&rules, Path::new("/repo/target"), false));
+        assert!(!is_ignored(&rules, Path::new("/repo/other"), true));
+    }
+
+    #[test]
+    fn negated_rule_re_includes_a_path() {
+        let rules = vec![
+            IgnoreRule {
+                root: PathBuf::from("/repo"),
+                pattern: "*.log".to_string(),
+                negated: false,
+                dir_only: false,
+                anchored: false,
+            },
+            IgnoreRule {
+                root: PathBuf::from("/repo"),
+                pattern: "keep.log".to_string(),
+                negated: true,
+                dir_only: false,
+                anchored: false,
+            },
+        ];
+
+        assert!(is_ignored(&rules, Path::new("/repo/debug.log"), false));
+        assert!(!is_ignored(&rules, Path::new("/repo/keep.log"), false));
+    }
+
+    #[test]
+    fn anchored_pattern_only_matches_relative_to_its_root() {
+        let rules = vec![IgnoreRule {
+            root: PathBuf::from("/repo"),
+            pattern: "build/output".to_string(),
+            negated: false,
+            dir_only: false,
+            anchored: true,
+        }];
+
+        assert!(is_ignored(&rules, Path::new("/repo/build/output"), false));
+        assert!(!is_ignored(&rules, Path::new("/repo/other/output"), false));
+    }
+}
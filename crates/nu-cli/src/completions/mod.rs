@@ -1,22 +1,46 @@
 mod base;
+mod boolean_completions;
 mod command_completions;
 mod completer;
 mod completion_common;
 mod completion_options;
+mod config_value_completions;
 mod custom_completions;
 mod directory_completions;
 mod dotnu_completions;
 mod file_completions;
 mod flag_completions;
+mod help_completions;
+mod ignore_files;
+mod module_member_completions;
+mod operator_completions;
+mod overlay_completions;
+mod pipeline_output_completions;
+mod recent_directory_completions;
+mod record_key_completions;
+mod unit_suffix_completions;
 mod variable_completions;
 
 pub use base::{Completer, SemanticSuggestion, SuggestionKind};
+pub use boolean_completions::BooleanCompletion;
 pub use command_completions::CommandCompletion;
-pub use completer::NuCompleter;
+#[cfg(feature = "async")]
+pub use completer::CompleteFuture;
+pub use completer::{CommandCompleterFn, CompleterKinds, CompletionMetrics, NuCompleter};
+pub use completion_common::PathFilter;
 pub use completion_options::{CompletionOptions, MatchAlgorithm, SortBy};
+pub use config_value_completions::ConfigValueCompletion;
 pub use custom_completions::CustomCompletion;
 pub use directory_completions::DirectoryCompletion;
 pub use dotnu_completions::DotNuCompletion;
-pub use file_completions::{file_path_completion, matches, FileCompletion};
+pub use file_completions::{file_path_completion, matches, matches_exactly, FileCompletion};
 pub use flag_completions::FlagCompletion;
-pub use variable_completions::VariableCompletion;
+pub use help_completions::HelpCompletion;
+pub use module_member_completions::ModuleMemberCompletion;
+pub use operator_completions::OperatorCompletion;
+pub use overlay_completions::OverlayCompletion;
+pub use pipeline_output_completions::PipelineOutputCompletion;
+pub use recent_directory_completions::RecentDirectoryCompletion;
+pub use record_key_completions::RecordKeyCompletion;
+pub use unit_suffix_completions::UnitSuffixCompletion;
+pub use variable_completions::{LiteralCellPathCompletion, VariableCompletion};
@@ -0,0 +1,65 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    ModuleId, Span,
+};
+use reedline::Suggestion;
+
+/// Completes the second argument of `use <module> <tab>` (and `overlay use
+/// <module> <tab>`) with the module's exported commands, aliases, and
+/// constants, e.g. `use std <tab>` suggesting `log`, `assert`, ....
+#[derive(Clone)]
+pub struct ModuleMemberCompletion {
+    module_id: ModuleId,
+}
+
+impl ModuleMemberCompletion {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self { module_id }
+    }
+}
+
+impl Completer for ModuleMemberCompletion {
+    fn fetch(
+        &mut self,
+        working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let module = working_set.get_module(self.module_id);
+
+        module
+            .decls
+            .keys()
+            .chain(module.constants.keys())
+            .chain(module.submodules.keys())
+            .filter(|name| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    name,
+                    &prefix,
+                )
+            })
+            .map(|name| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: String::from_utf8_lossy(name).to_string(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: None,
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SuggestionKind};
+use nu_parser::FlatShape;
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span, Type,
+};
+use reedline::Suggestion;
+
+/// Operators such as `in`, `not-in`, `starts-with`, `ends-with`, `has` and the
+/// comparison operators (`==`, `<`, `>`, ...) which can follow an operand in
+/// expression position, e.g. `$x | where foo `.
+///
+/// The candidates offered depend on the shape of the operand to their left:
+/// string-like operands additionally get `starts-with`/`ends-with`/`has`/
+/// `=~`/`!~`, container-like operands (list, record, table) get `has` in
+/// place of the comparison operators, which don't apply to them, and numeric operands
+/// (int, float) additionally get the arithmetic operators (`+`, `-`, `*`,
+/// `/`, `fdiv`, `mod`, `**`).
+#[derive(Clone)]
+pub struct OperatorCompletion {
+    operand_shape: FlatShape,
+}
+
+impl OperatorCompletion {
+    pub fn new(operand_shape: FlatShape) -> Self {
+        Self { operand_shape }
+    }
+}
+
+impl Completer for OperatorCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let candidates: &[&str] = match self.operand_shape {
+            FlatShape::String | FlatShape::RawString | FlatShape::StringInterpolation => &[
+                "in",
+                "not-in",
+                "starts-with",
+                "ends-with",
+                "has",
+                "==",
+                "!=",
+                "<",
+                "<=",
+                ">",
+                ">=",
+                "=~",
+                "!~",
+            ],
+            FlatShape::List | FlatShape::Table | FlatShape::Record => &["in", "not-in", "has"],
+            FlatShape::Int | FlatShape::Float => &[
+                "+", "-", "*", "/", "fdiv", "mod", "**", "in", "not-in", "==", "!=", "<", "<=",
+                ">", ">=",
+            ],
+            _ => &["in", "not-in", "==", "!=", "<", "<=", ">", ">="],
+        };
+
+        candidates
+            .iter()
+            .filter(|candidate| {
+                options
+                    .match_algorithm
+                    .matches_u8(candidate.as_bytes(), &prefix)
+            })
+            .map(|candidate| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: candidate.to_string(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: Some(SuggestionKind::Type(Type::Bool)),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SortBy};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span,
+};
+use reedline::Suggestion;
+
+/// Completes the overlay-name argument of `overlay hide <tab>` with the
+/// names of the currently active overlays, in the order they were
+/// activated.
+#[derive(Clone)]
+pub struct OverlayCompletion;
+
+impl OverlayCompletion {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Completer for OverlayCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        stack
+            .active_overlays
+            .iter()
+            .filter(|name| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    name.as_bytes(),
+                    &prefix,
+                )
+            })
+            .map(|name| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: name.clone(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: None,
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+
+    // Keep activation order instead of the default alphabetical sort.
+    fn get_sort_by(&self) -> SortBy {
+        SortBy::None
+    }
+}
@@ -0,0 +1,60 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span,
+};
+use reedline::Suggestion;
+
+/// Completes a bare cell path argument (e.g. `get `, `select `) against the
+/// columns the preceding pipeline element is declared to output, so
+/// `ls | get ` can suggest `name`, `type`, `size`, ... without running `ls`.
+#[derive(Clone)]
+pub struct PipelineOutputCompletion {
+    columns: Vec<String>,
+}
+
+impl PipelineOutputCompletion {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self { columns }
+    }
+}
+
+impl Completer for PipelineOutputCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        self.columns
+            .iter()
+            .filter(|column| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    column.as_bytes(),
+                    &prefix,
+                )
+            })
+            .map(|column| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: column.clone(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: None,
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
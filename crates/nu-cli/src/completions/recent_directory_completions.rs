@@ -0,0 +1,74 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SortBy, SuggestionKind};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span, Type,
+};
+use reedline::Suggestion;
+use std::collections::HashSet;
+
+/// Completes `cd`'s argument, before anything has been typed, with recently
+/// visited directories -- read from `$env.DIRS_LIST` (the ring buffer the
+/// `dirs` standard module maintains), most recently added first. The
+/// dispatcher in [`super::completer`] only reaches for this once the typed
+/// token is empty; once the user starts narrowing down a path, normal
+/// [`super::DirectoryCompletion`] takes over instead.
+#[derive(Clone, Default)]
+pub struct RecentDirectoryCompletion {}
+
+impl RecentDirectoryCompletion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Completer for RecentDirectoryCompletion {
+    fn fetch(
+        &mut self,
+        working_set: &StateWorkingSet,
+        stack: &Stack,
+        _prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        _options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let Some(dirs_list) = stack
+            .get_env_var(working_set.permanent_state, "DIRS_LIST")
+            .and_then(|value| value.as_list().map(|list| list.to_vec()).ok())
+        else {
+            return vec![];
+        };
+
+        let current_dir = working_set.permanent_state.current_work_dir();
+        let mut seen = HashSet::new();
+
+        dirs_list
+            .into_iter()
+            .rev()
+            .filter_map(|value| value.coerce_into_string().ok())
+            .filter(|dir| *dir != current_dir && seen.insert(dir.clone()))
+            .map(|dir| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: dir,
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: false,
+                },
+                kind: Some(SuggestionKind::Type(Type::String)),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+
+    // Preserve `$env.DIRS_LIST` recency order instead of the default
+    // alphabetical sort.
+    fn get_sort_by(&self) -> SortBy {
+        SortBy::None
+    }
+}
@@ -0,0 +1,61 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SuggestionKind};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span, Type,
+};
+use reedline::Suggestion;
+
+/// Completes field names for a record literal typed under a positional
+/// argument whose declared shape is `record<...>`, e.g. `mycmd { <tab>`
+/// suggesting the fields the callee's signature expects there. `fields` is
+/// pre-filtered by the caller to exclude any key already set in the literal.
+#[derive(Clone)]
+pub struct RecordKeyCompletion {
+    fields: Vec<String>,
+}
+
+impl RecordKeyCompletion {
+    pub fn new(fields: Vec<String>) -> Self {
+        Self { fields }
+    }
+}
+
+impl Completer for RecordKeyCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        self.fields
+            .iter()
+            .filter(|field| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    field.as_bytes(),
+                    &prefix,
+                )
+            })
+            .map(|field| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: field.clone(),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: false,
+                },
+                kind: Some(SuggestionKind::Type(Type::String)),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
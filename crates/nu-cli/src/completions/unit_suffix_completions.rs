@@ -0,0 +1,95 @@
+use crate::completions::{Completer, CompletionOptions, SemanticSuggestion, SuggestionKind};
+use nu_parser::{DURATION_UNIT_GROUPS, FILESIZE_UNIT_GROUPS};
+use nu_protocol::{
+    engine::{Stack, StateWorkingSet},
+    Span, Type,
+};
+use reedline::Suggestion;
+
+/// Completes a bare number under a `duration`- or `filesize`-typed argument
+/// with each valid unit suffix, e.g. `sleep 5<tab>` -> `5ns`, `5sec`,
+/// `5min`, ... A number without a unit doesn't parse as either type, so this
+/// only ever sees a plain, as-typed-so-far numeric prefix.
+#[derive(Clone)]
+pub struct UnitSuffixCompletion {
+    units: Vec<String>,
+    kind: Type,
+}
+
+impl UnitSuffixCompletion {
+    pub fn duration() -> Self {
+        Self {
+            units: DURATION_UNIT_GROUPS
+                .iter()
+                .map(|(_, name, _)| name.to_string())
+                .collect(),
+            kind: Type::Duration,
+        }
+    }
+
+    pub fn filesize() -> Self {
+        Self {
+            units: FILESIZE_UNIT_GROUPS
+                .iter()
+                .map(|(_, name, _)| name.to_ascii_lowercase())
+                .collect(),
+            kind: Type::Filesize,
+        }
+    }
+}
+
+impl Completer for UnitSuffixCompletion {
+    fn fetch(
+        &mut self,
+        _working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let Ok(typed) = std::str::from_utf8(&prefix) else {
+            return vec![];
+        };
+
+        // Split what's been typed into the number (kept as-is in every
+        // suggestion) and whatever unit letters already follow it, so
+        // e.g. `5s` still narrows down to `5sec` instead of only matching
+        // on a bare `5`.
+        let number_len = typed
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+            .unwrap_or(typed.len());
+        if number_len == 0 {
+            return vec![];
+        }
+        let (number, typed_unit) = typed.split_at(number_len);
+
+        self.units
+            .iter()
+            .filter(|unit| {
+                options.match_algorithm.matches_u8_insensitive(
+                    options.case_sensitive,
+                    unit.as_bytes(),
+                    typed_unit.as_bytes(),
+                )
+            })
+            .map(|unit| SemanticSuggestion {
+                suggestion: Suggestion {
+                    value: format!("{number}{unit}"),
+                    description: None,
+                    style: None,
+                    extra: None,
+                    span: reedline::Span {
+                        start: span.start - offset,
+                        end: span.end - offset,
+                    },
+                    append_whitespace: true,
+                },
+                kind: Some(SuggestionKind::Type(self.kind.clone())),
+                match_score: None,
+                is_unambiguous_match: false,
+            })
+            .collect()
+    }
+}
@@ -4,7 +4,8 @@ use crate::completions::{
 use nu_engine::{column::get_columns, eval_variable};
 use nu_protocol::{
     engine::{Stack, StateWorkingSet},
-    Span, Value,
+    eval_const::eval_const_subexpression,
+    BlockId, PipelineData, Span, VarId, Value,
 };
 use reedline::Suggestion;
 use std::str;
@@ -12,11 +13,79 @@ use std::str;
 #[derive(Clone)]
 pub struct VariableCompletion {
     var_context: (Vec<u8>, Vec<Vec<u8>>), // tuple with $var and the sublevels (.b.c.d)
+    /// Parameters of every closure enclosing the cursor, e.g. `spans` inside
+    /// `{|spans| $sp}` -- these aren't reachable through `working_set`'s
+    /// scope frames by the time completion runs, so the caller resolves them
+    /// upfront by walking the parsed closures directly.
+    closure_params: Vec<(Vec<u8>, VarId)>,
 }
 
 impl VariableCompletion {
-    pub fn new(var_context: (Vec<u8>, Vec<Vec<u8>>)) -> Self {
-        Self { var_context }
+    pub fn new(
+        var_context: (Vec<u8>, Vec<Vec<u8>>),
+        closure_params: Vec<(Vec<u8>, VarId)>,
+    ) -> Self {
+        Self {
+            var_context,
+            closure_params,
+        }
+    }
+}
+
+/// Completes cell paths into a literal, constant-foldable subexpression, e.g.
+/// `({a: {b: {c: 1}}}).a.b.` suggesting `c`. Unlike [`VariableCompletion`],
+/// there's no variable to look up on the stack, so the head block is
+/// re-evaluated with [`eval_const_subexpression`], which errors out (instead
+/// of running arbitrary code) on anything that isn't a compile-time constant.
+#[derive(Clone)]
+pub struct LiteralCellPathCompletion {
+    head_block_id: BlockId,
+    sublevels: Vec<Vec<u8>>,
+}
+
+impl LiteralCellPathCompletion {
+    pub fn new(head_block_id: BlockId, sublevels: Vec<Vec<u8>>) -> Self {
+        Self {
+            head_block_id,
+            sublevels,
+        }
+    }
+}
+
+impl Completer for LiteralCellPathCompletion {
+    fn fetch(
+        &mut self,
+        working_set: &StateWorkingSet,
+        _stack: &Stack,
+        prefix: Vec<u8>,
+        span: Span,
+        offset: usize,
+        _pos: usize,
+        options: &CompletionOptions,
+    ) -> Vec<SemanticSuggestion> {
+        let current_span = reedline::Span {
+            start: span.start - offset,
+            end: span.end - offset,
+        };
+
+        let block = working_set.get_block(self.head_block_id);
+        let Ok(value) = eval_const_subexpression(working_set, block, PipelineData::empty(), span)
+            .and_then(|data| data.into_value(span))
+        else {
+            return vec![];
+        };
+
+        let mut output = vec![];
+        for suggestion in nested_suggestions(&value, &self.sublevels, current_span) {
+            if options.match_algorithm.matches_u8_insensitive(
+                options.case_sensitive,
+                suggestion.suggestion.value.as_bytes(),
+                &prefix,
+            ) {
+                output.push(suggestion);
+            }
+        }
+        output
     }
 }
 
@@ -58,6 +127,49 @@ impl Completer for VariableCompletion {
                     let nested_levels: Vec<Vec<u8>> =
                         self.var_context.1.clone().into_iter().skip(1).collect();
 
+                    // The hooks config is schema-driven rather than free-form: known hook
+                    // names (and, one level deeper, the env var names for `env_change`)
+                    // should be offered even before the user has set any hooks, since
+                    // an unset hook simply isn't present in the reconstructed record.
+                    if target_var_str == "config"
+                        && nested_levels.first().map(Vec::as_slice) == Some(b"hooks")
+                    {
+                        for suggestion in
+                            hooks_suggestions(&nested_levels[1..], working_set, stack, current_span)
+                        {
+                            if options.match_algorithm.matches_u8_insensitive(
+                                options.case_sensitive,
+                                suggestion.suggestion.value.as_bytes(),
+                                &prefix,
+                            ) {
+                                output.push(suggestion);
+                            }
+                        }
+
+                        return output;
+                    }
+
+                    // Likewise, `completions` is schema-driven: known field
+                    // names (and, one level deeper, `external`'s fields)
+                    // should be offered even before the user has customized
+                    // anything under `$env.config.completions`.
+                    if target_var_str == "config"
+                        && nested_levels.first().map(Vec::as_slice) == Some(b"completions")
+                    {
+                        for suggestion in completions_suggestions(&nested_levels[1..], current_span)
+                        {
+                            if options.match_algorithm.matches_u8_insensitive(
+                                options.case_sensitive,
+                                suggestion.suggestion.value.as_bytes(),
+                                &prefix,
+                            ) {
+                                output.push(suggestion);
+                            }
+                        }
+
+                        return output;
+                    }
+
                     if let Some(val) = env_vars.get(&target_var_str) {
                         for suggestion in nested_suggestions(val, &nested_levels, current_span) {
                             if options.match_algorithm.matches_u8_insensitive(
@@ -69,6 +181,24 @@ impl Completer for VariableCompletion {
                             }
                         }
 
+                        // Still offer a renamed key's old name at
+                        // `$env.config.<tab>`, so a user typing from memory
+                        // (or an old script) gets pointed at the new one
+                        // instead of just a "column not found" error later.
+                        if target_var_str == "config" && nested_levels.is_empty() {
+                            for suggestion in
+                                deprecated_config_key_suggestions(current_span)
+                            {
+                                if options.match_algorithm.matches_u8_insensitive(
+                                    options.case_sensitive,
+                                    suggestion.suggestion.value.as_bytes(),
+                                    &prefix,
+                                ) {
+                                    output.push(suggestion);
+                                }
+                            }
+                        }
+
                         return output;
                     }
                 } else {
@@ -82,13 +212,15 @@ impl Completer for VariableCompletion {
                             output.push(SemanticSuggestion {
                                 suggestion: Suggestion {
                                     value: env_var.0,
-                                    description: None,
+                                    description: env_var_description(&env_var.1),
                                     style: None,
                                     extra: None,
                                     span: current_span,
                                     append_whitespace: false,
                                 },
                                 kind: Some(SuggestionKind::Type(env_var.1.get_type())),
+                                match_score: None,
+                                is_unambiguous_match: false,
                             });
                         }
                     }
@@ -162,6 +294,31 @@ impl Completer for VariableCompletion {
                     },
                     // TODO is there a way to get the VarId to get the type???
                     kind: None,
+                    match_score: None,
+                    is_unambiguous_match: false,
+                });
+            }
+        }
+
+        // Enclosing closures' own parameters, e.g. `spans` inside `{|spans|
+        // $sp}`. These aren't part of `working_set`'s scope frames below.
+        for (name, var_id) in &self.closure_params {
+            if options
+                .match_algorithm
+                .matches_u8_insensitive(options.case_sensitive, name, &prefix)
+            {
+                output.push(SemanticSuggestion {
+                    suggestion: Suggestion {
+                        value: String::from_utf8_lossy(name).to_string(),
+                        description: None,
+                        style: None,
+                        extra: None,
+                        span: current_span,
+                        append_whitespace: false,
+                    },
+                    kind: Some(SuggestionKind::Type(working_set.get_variable(*var_id).ty.clone())),
+                    match_score: None,
+                    is_unambiguous_match: false,
                 });
             }
         }
@@ -190,6 +347,8 @@ impl Completer for VariableCompletion {
                             kind: Some(SuggestionKind::Type(
                                 working_set.get_variable(*v.1).ty.clone(),
                             )),
+                            match_score: None,
+                            is_unambiguous_match: false,
                         });
                     }
                 }
@@ -221,6 +380,8 @@ impl Completer for VariableCompletion {
                         kind: Some(SuggestionKind::Type(
                             working_set.get_variable(*v.1).ty.clone(),
                         )),
+                        match_score: None,
+                        is_unambiguous_match: false,
                     });
                 }
             }
@@ -232,6 +393,131 @@ impl Completer for VariableCompletion {
     }
 }
 
+// Known `$env.config.hooks` field names, kept in sync with `nu_protocol::config::hooks::Hooks`.
+const HOOK_NAMES: &[&str] = &[
+    "pre_prompt",
+    "pre_execution",
+    "env_change",
+    "display_output",
+    "command_not_found",
+];
+
+// Completions for `$env.config.hooks` and, one level deeper, `$env.config.hooks.env_change`.
+fn hooks_suggestions(
+    sublevels: &[Vec<u8>],
+    working_set: &StateWorkingSet,
+    stack: &Stack,
+    current_span: reedline::Span,
+) -> Vec<SemanticSuggestion> {
+    let names: Vec<String> = match sublevels.first().map(Vec::as_slice) {
+        None => HOOK_NAMES.iter().map(|name| name.to_string()).collect(),
+        Some(b"env_change") if sublevels.len() == 1 => stack
+            .get_env_vars(working_set.permanent_state)
+            .into_keys()
+            .collect(),
+        _ => vec![],
+    };
+
+    names
+        .into_iter()
+        .map(|value| SemanticSuggestion {
+            suggestion: Suggestion {
+                value,
+                description: None,
+                style: None,
+                extra: None,
+                span: current_span,
+                append_whitespace: false,
+            },
+            kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
+        })
+        .collect()
+}
+
+// Known `$env.config.completions` field names, kept in sync with the
+// `"completions" => { ... }` parsing arm in `nu_protocol::config`.
+const COMPLETIONS_NAMES: &[&str] = &[
+    "case_sensitive",
+    "case_sensitive_paths",
+    "quick",
+    "partial",
+    "algorithm",
+    "sort",
+    "external",
+    "use_ls_colors",
+    "hidden_files",
+    "append_slash",
+    "use_ignore_files",
+    "dirs_first",
+    "transform",
+    "path_roots",
+    "fuzzy_anchor",
+    "show_examples",
+];
+
+// Known `$env.config.completions.external` field names.
+const COMPLETIONS_EXTERNAL_NAMES: &[&str] = &["enable", "max_results", "completer", "timeout"];
+
+// Completions for `$env.config.completions` and, one level deeper,
+// `$env.config.completions.external`.
+fn completions_suggestions(
+    sublevels: &[Vec<u8>],
+    current_span: reedline::Span,
+) -> Vec<SemanticSuggestion> {
+    let names: &[&str] = match sublevels.first().map(Vec::as_slice) {
+        None => COMPLETIONS_NAMES,
+        Some(b"external") if sublevels.len() == 1 => COMPLETIONS_EXTERNAL_NAMES,
+        _ => &[],
+    };
+
+    names
+        .iter()
+        .map(|value| SemanticSuggestion {
+            suggestion: Suggestion {
+                value: value.to_string(),
+                description: None,
+                style: None,
+                extra: None,
+                span: current_span,
+                append_whitespace: false,
+            },
+            kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
+        })
+        .collect()
+}
+
+// Top-level `$env.config` keys that were renamed to a new path. Kept as a
+// small, explicit list rather than trying to derive it from `Config`'s
+// parsing code, since a renamed key by definition no longer has a field
+// there to introspect.
+const DEPRECATED_CONFIG_KEYS: &[(&str, &str)] = &[("history_file_format", "history.file_format")];
+
+// Suggests a deprecated `$env.config` key's old name, with a description
+// pointing at its replacement, so `$env.config.<tab>` still surfaces it
+// alongside the current fields.
+fn deprecated_config_key_suggestions(current_span: reedline::Span) -> Vec<SemanticSuggestion> {
+    DEPRECATED_CONFIG_KEYS
+        .iter()
+        .map(|(old_key, new_key)| SemanticSuggestion {
+            suggestion: Suggestion {
+                value: old_key.to_string(),
+                description: Some(format!("deprecated; use {new_key} instead")),
+                style: None,
+                extra: None,
+                span: current_span,
+                append_whitespace: false,
+            },
+            kind: None,
+            match_score: None,
+            is_unambiguous_match: false,
+        })
+        .collect()
+}
+
 // Find recursively the values for sublevels
 // if no sublevels are set it returns the current value
 fn nested_suggestions(
@@ -257,6 +543,8 @@ fn nested_suggestions(
                         append_whitespace: false,
                     },
                     kind: Some(kind.clone()),
+                    match_score: None,
+                    is_unambiguous_match: false,
                 });
             }
 
@@ -274,6 +562,8 @@ fn nested_suggestions(
                         append_whitespace: false,
                     },
                     kind: Some(kind.clone()),
+                    match_score: None,
+                    is_unambiguous_match: false,
                 });
             }
 
@@ -283,6 +573,28 @@ fn nested_suggestions(
     }
 }
 
+// Cap on how much of a string env var's value is shown in its completion
+// description, so secrets-like values (tokens, keys) don't leak in full.
+const ENV_VAR_PREVIEW_LEN: usize = 40;
+
+// Builds a short "<type>: <value preview>" description for an env var
+// suggestion. Only string values get a preview; other types just show
+// their type name, since previewing e.g. a whole record isn't useful here.
+fn env_var_description(val: &Value) -> Option<String> {
+    let type_name = val.get_type().to_string();
+    match val.as_str() {
+        Ok(s) => {
+            let preview: String = s.chars().take(ENV_VAR_PREVIEW_LEN).collect();
+            let truncated = s.chars().count() > ENV_VAR_PREVIEW_LEN;
+            Some(format!(
+                "{type_name}: {preview}{}",
+                if truncated { "…" } else { "" }
+            ))
+        }
+        Err(_) => Some(type_name),
+    }
+}
+
 // Extracts the recursive value (e.g: $var.a.b.c)
 fn recursive_value(val: &Value, sublevels: &[Vec<u8>]) -> Result<Value, Span> {
     // Go to next sublevel
@@ -11,11 +11,18 @@ mod prompt_update;
 mod reedline_config;
 mod repl;
 mod syntax_highlight;
+#[cfg(feature = "test-support")]
+mod test_support;
 mod util;
 mod validation;
 
 pub use commands::add_cli_context;
-pub use completions::{FileCompletion, NuCompleter, SemanticSuggestion, SuggestionKind};
+#[cfg(feature = "async")]
+pub use completions::CompleteFuture;
+pub use completions::{
+    CommandCompleterFn, CompleterKinds, CompletionMetrics, FileCompletion, NuCompleter,
+    PathFilter, SemanticSuggestion, SuggestionKind,
+};
 pub use config_files::eval_config_contents;
 pub use eval_cmds::{evaluate_commands, EvaluateCommandsOpts};
 pub use eval_file::evaluate_file;
@@ -26,6 +33,8 @@ pub use print::Print;
 pub use prompt::NushellPrompt;
 pub use repl::evaluate_repl;
 pub use syntax_highlight::NuHighlighter;
+#[cfg(feature = "test-support")]
+pub use test_support::match_suggestions;
 pub use util::{eval_source, gather_parent_env_vars};
 pub use validation::NuValidator;
 
@@ -0,0 +1,70 @@
+use reedline::Suggestion;
+
+/// Asserts that `suggestions` (in order) has exactly one entry per string in
+/// `expected` (in the same order), comparing each against
+/// [`Suggestion::value`]. Panics with a diff of both lists on mismatch.
+///
+/// Mirrors the `match_suggestions` test helper `nu-cli` uses internally, so
+/// downstream crates that embed [`NuCompleter`](crate::NuCompleter) (e.g.
+/// plugins with their own custom completions) can write completion tests
+/// the same way without duplicating the comparison logic.
+pub fn match_suggestions(expected: &[&str], suggestions: &[Suggestion]) {
+    let expected_len = expected.len();
+    let suggestions_len = suggestions.len();
+    if expected_len != suggestions_len {
+        panic!(
+            "\nexpected {expected_len} suggestions but got {suggestions_len}: \n\
+            Suggestions: {suggestions:#?} \n\
+            Expected: {expected:#?}\n"
+        )
+    }
+    expected
+        .iter()
+        .zip(suggestions)
+        .for_each(|(expected, suggestion)| {
+            assert_eq!(*expected, suggestion.value);
+        });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_when_values_and_order_agree() {
+        let suggestions = vec![
+            Suggestion {
+                value: "a".into(),
+                ..Suggestion::default()
+            },
+            Suggestion {
+                value: "b".into(),
+                ..Suggestion::default()
+            },
+        ];
+
+        match_suggestions(&["a", "b"], &suggestions);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_value_mismatch() {
+        let suggestions = vec![Suggestion {
+            value: "a".into(),
+            ..Suggestion::default()
+        }];
+
+        match_suggestions(&["b"], &suggestions);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_length_mismatch() {
+        let suggestions = vec![Suggestion {
+            value: "a".into(),
+            ..Suggestion::default()
+        }];
+
+        match_suggestions(&["a", "b"], &suggestions);
+    }
+}
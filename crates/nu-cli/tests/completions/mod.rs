@@ -1,6 +1,6 @@
 pub mod support;
 
-use nu_cli::NuCompleter;
+use nu_cli::{CompleterKinds, NuCompleter};
 use nu_engine::eval_block;
 use nu_parser::parse;
 use nu_protocol::{debugger::WithoutDebug, engine::StateWorkingSet, PipelineData};
@@ -8,7 +8,8 @@ use reedline::{Completer, Suggestion};
 use rstest::{fixture, rstest};
 use std::{
     path::{PathBuf, MAIN_SEPARATOR},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 use support::{
     completions_helpers::{new_partial_engine, new_quote_engine},
@@ -42,6 +43,27 @@ fn completer_strings() -> NuCompleter {
     NuCompleter::new(Arc::new(engine), Arc::new(stack))
 }
 
+#[fixture]
+fn def_completer() -> NuCompleter {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    // Add record value as example
+    let record = r#"
+        def animals [] { [ "cat", "dog", "eel" ] }
+        def spam [
+            animal: string@animals
+            --foo (-f): string@animals
+            -b: string@animals
+            --enabled
+        ] { }
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    // Instantiate a new completer
+    NuCompleter::new(Arc::new(engine), Arc::new(stack))
+}
+
 #[fixture]
 fn extern_completer() -> NuCompleter {
     // Create a new engine
@@ -112,6 +134,182 @@ fn variables_single_dash_argument_with_flagcompletion(mut completer: NuCompleter
     match_suggestions(expected, suggestions);
 }
 
+#[test]
+fn flag_completion_still_fires_after_a_switch_that_takes_no_value() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = r#"
+        def tst [
+            --color: string
+            --loud
+        ] {}
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `--loud` is a switch, so it doesn't consume `-` as its value -- `-`
+    // is a new flag position.
+    let line = "tst --loud -";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec![
+        "--color".into(),
+        "--help".into(),
+        "--loud".into(),
+        "-h".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn flag_completion_is_suppressed_right_after_a_value_taking_flag() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = r#"
+        def tst [
+            --color: string
+            --loud
+        ] {}
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `--color` expects a value, so `-` right after it is that value, not a
+    // new flag position -- none of `tst`'s flags should be suggested here.
+    let line = "tst --color -";
+    let suggestions = completer.complete(line, line.len());
+    assert!(!suggestions.iter().any(|s| s.value == "--loud"));
+    assert!(!suggestions.iter().any(|s| s.value == "--help"));
+}
+
+#[test]
+fn flag_completion_fires_inside_an_if_block_body() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = r#"
+        def tst [
+            --color: string
+            --loud
+        ] {}
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `tst`'s flags should be suggested here, not `if`'s -- the cursor is
+    // inside the block body, not at the top-level call.
+    let line = "if true { tst - }";
+    let suggestions = completer.complete(line, "if true { tst -".len());
+    let expected: Vec<String> = vec![
+        "--color".into(),
+        "--help".into(),
+        "--loud".into(),
+        "-h".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn flag_completion_fires_inside_a_try_block_body() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = r#"
+        def tst [
+            --color: string
+            --loud
+        ] {}
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "try { tst - }";
+    let suggestions = completer.complete(line, "try { tst -".len());
+    let expected: Vec<String> = vec![
+        "--color".into(),
+        "--help".into(),
+        "--loud".into(),
+        "-h".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn incremental_completion_matches_the_non_cached_path() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Warm the fast-path cache with a shorter query, then grow it by typing
+    // one more character into the same token.
+    completer.complete("l", 1);
+    let cached_result = completer.complete("ls", 2);
+
+    // A completer with no prior history computes the same query the normal
+    // (non-cached) way.
+    let (_, _, engine, stack) = new_engine();
+    let mut fresh_completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    let fresh_result = fresh_completer.complete("ls", 2);
+
+    let cached_values: Vec<&String> = cached_result.iter().map(|s| &s.value).collect();
+    let fresh_values: Vec<&String> = fresh_result.iter().map(|s| &s.value).collect();
+    assert_eq!(cached_values, fresh_values);
+    assert!(!cached_values.is_empty());
+}
+
+#[test]
+fn complete_in_block_matches_complete_for_a_pre_parsed_block() {
+    // `complete` parses `line` into a throwaway `Block`/`StateWorkingSet`
+    // internally, appending a fake trailing character so the cursor always
+    // lands inside the token being completed. An LSP server that keeps the
+    // document parsed can do the same parsing itself and hand the result to
+    // `complete_in_block`, to skip nu-cli reparsing it.
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = "def tst [--mod -s] {}";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let engine_state = Arc::new(engine);
+    let mut completer = NuCompleter::new(engine_state.clone(), Arc::new(stack));
+
+    let expected: Vec<String> = vec!["--help".into(), "--mod".into(), "-h".into(), "-s".into()];
+    let from_line = completer.complete("tst -", 5);
+    match_suggestions(expected.clone(), from_line);
+
+    let mut working_set = StateWorkingSet::new(&engine_state);
+    let offset = working_set.next_span_start();
+    let mut line = "tst -".to_string();
+    let pos = offset + line.len();
+    line.push('a');
+    let block = parse(&mut working_set, Some("completer"), line.as_bytes(), false);
+
+    let from_block: Vec<Suggestion> = completer
+        .complete_in_block(&working_set, &block, pos)
+        .into_iter()
+        .map(|s| s.suggestion)
+        .collect();
+    match_suggestions(expected, from_block);
+}
+
+#[test]
+fn file_and_directory_completions_can_be_disabled_at_construction() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    // Add record value as example
+    let record = "def tst [--mod -s] {}";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::with_disabled(
+        Arc::new(engine),
+        Arc::new(stack),
+        CompleterKinds {
+            file: true,
+            directory: true,
+            external: false,
+        },
+    );
+
+    // File completion is disabled, so `ls ` returns nothing instead of scanning the cwd
+    let suggestions = completer.complete("ls ", 3);
+    assert!(suggestions.is_empty());
+
+    // Flag completion is unaffected
+    let suggestions = completer.complete("tst -", 5);
+    let expected: Vec<String> = vec!["--help".into(), "--mod".into(), "-h".into(), "-s".into()];
+    match_suggestions(expected, suggestions);
+}
+
 #[rstest]
 fn variables_command_with_commandcompletion(mut completer_strings: NuCompleter) {
     let suggestions = completer_strings.complete("my-c ", 4);
@@ -135,6 +333,92 @@ fn variables_customcompletion_subcommands_with_customcompletion_2(
     match_suggestions(expected, suggestions);
 }
 
+#[test]
+fn custom_completion_options_max_results_truncates_after_sorting() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"
+        def animals [] {
+            {
+                completions: ["eel", "cat", "dog"],
+                options: {
+                    sort: true
+                    max_results: 2
+                }
+            }
+        }
+        def my-command [animal: string@animals] { print $animal }
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("my-command ", 11);
+    let expected: Vec<String> = vec!["cat".into(), "dog".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn custom_completion_prefers_exact_case_match_when_case_insensitive() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"
+        def letters [] {
+            {
+                completions: ["ABCdef", "Abcdef"],
+                options: {
+                    sort: true
+                    case_sensitive: false
+                }
+            }
+        }
+        def my-command [word: string@letters] { print $word }
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("my-command Abc", 15);
+    let expected: Vec<String> = vec!["Abcdef".into(), "ABCdef".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn custom_completion_options_prefix_strips_shared_prefix_and_narrows_span() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"
+        def branches [] {
+            {
+                completions: ["feat/x", "feat/y"],
+                options: {
+                    prefix: "feat/"
+                }
+            }
+        }
+        def my-command [branch: string@branches] { print $branch }
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // The `feat/` the user already typed is part of the buffer, so the
+    // returned suggestion should only cover the differentiating `x`, with a
+    // zero-width replacement span sitting right at the cursor instead of
+    // re-covering the `feat/` that's already there.
+    let line = "my-command feat/";
+    let suggestions = completer.complete(line, line.len());
+
+    let suggestion = suggestions
+        .iter()
+        .find(|s| s.value == "x")
+        .expect("prefix should be stripped from the suggestion value");
+    assert_eq!(suggestion.span.start, suggestion.span.end);
+}
+
 #[test]
 fn dotnu_completions() {
     // Create a new engine
@@ -177,6 +461,199 @@ fn dotnu_completions() {
     assert_eq!("directory_completion/", suggestions.get(1).unwrap().value);
 }
 
+#[test]
+fn dotnu_completions_dedupes_cwd_and_lib_dirs() {
+    // Create a new engine
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+
+    // Point NU_LIB_DIRS at the same directory as the cwd, so every .nu file
+    // there is reachable through both search paths.
+    stack.add_env_var(
+        "NU_LIB_DIRS".to_string(),
+        nu_protocol::Value::list(
+            vec![nu_protocol::Value::test_string(dir_str)],
+            nu_protocol::Span::test_data(),
+        ),
+    );
+    assert!(engine.merge_env(&mut stack, &dir).is_ok());
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Test use completion
+    let completion_str = "use ".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+
+    assert_eq!(2, suggestions.len());
+    assert_eq!("custom_completion.nu", suggestions.first().unwrap().value);
+    #[cfg(windows)]
+    assert_eq!("directory_completion\\", suggestions.get(1).unwrap().value);
+    #[cfg(not(windows))]
+    assert_eq!("directory_completion/", suggestions.get(1).unwrap().value);
+}
+
+#[test]
+fn dotnu_completion_does_not_double_append_extension() {
+    // Create a new engine
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // The partial already contains most of the `.nu` extension; the
+    // suggested filename should still only have it once.
+    let completion_str = "use custom_completion.n".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+
+    assert_eq!(1, suggestions.len());
+    assert_eq!("custom_completion.nu", suggestions[0].value);
+}
+
+#[test]
+fn dotnu_completion_is_flagged_unambiguous_when_it_is_the_only_match() {
+    // Create a new engine
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // "custom_completion." only matches "custom_completion.nu", unlike a
+    // bare "use " which also offers "directory_completion/".
+    let completion_str = "use custom_completion.".to_string();
+    let suggestions = completer.fetch_completions_at(&completion_str, completion_str.len());
+
+    assert_eq!(1, suggestions.len());
+    assert_eq!("custom_completion.nu", suggestions[0].suggestion.value);
+    assert!(suggestions[0].is_unambiguous_match);
+}
+
+#[test]
+fn use_completions_module_members() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let module = r#"module greetings {
+        export def hello [] { "hello" }
+        export def goodbye [] { "goodbye" }
+    }"#;
+    assert!(support::merge_input(module.as_bytes(), &mut engine, &mut stack, dir.clone()).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Once the module is known, `use greetings <tab>` should suggest its
+    // exports rather than falling back to file completion.
+    let completion_str = "use greetings ".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert_eq!(2, suggestions.len());
+    assert!(suggestions.iter().any(|s| s.value == "hello"));
+    assert!(suggestions.iter().any(|s| s.value == "goodbye"));
+
+    // The first argument itself is unaffected -- it's still plain filesystem
+    // completion for the module/file being imported.
+    let completion_str = "use custom_completion".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == "custom_completion.nu"));
+
+    // A second argument after something that isn't a known module name (a
+    // plain file, here) doesn't get module-member suggestions either.
+    let completion_str = "use custom_completion.nu ".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert!(!suggestions.iter().any(|s| s.value == "hello"));
+}
+
+#[test]
+fn module_qualified_command_completion_suggests_members() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let module = r#"module greetings {
+        export def hello [] { "hello" }
+        export def goodbye [] { "goodbye" }
+    }"#;
+    assert!(support::merge_input(module.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Typing the module name as a command head, without `use`, should still
+    // offer its members rather than the entire unfiltered command list.
+    let completion_str = "greetings ".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert_eq!(2, suggestions.len());
+    assert!(suggestions.iter().any(|s| s.value == "hello"));
+    assert!(suggestions.iter().any(|s| s.value == "goodbye"));
+
+    // Partial module paths complete too.
+    let completion_str = "greetings go".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    match_suggestions(vec!["goodbye".to_string()], suggestions);
+}
+
+#[test]
+fn help_completions_suggest_commands_and_subtopics() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let completion_str = "help ls".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert!(suggestions.iter().any(|s| s.value == "ls"));
+
+    let completion_str = "help mod".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert!(suggestions.iter().any(|s| s.value == "modules"));
+}
+
+#[test]
+fn show_examples_attaches_first_example_to_command_description() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.show_examples = true",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "cha";
+    let suggestions = completer.complete(line, line.len());
+    let chain = suggestions
+        .iter()
+        .find(|s| s.value == "chain")
+        .expect("expected a suggestion for chain");
+    let description = chain.description.as_deref().unwrap_or_default();
+    assert!(
+        description.contains("[1 2 3 4 5] | chain 2"),
+        "description should include chain's first example, got: {description}"
+    );
+
+    // Off by default: the description is just the usual usage string.
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete(line, line.len());
+    let chain = suggestions
+        .iter()
+        .find(|s| s.value == "chain")
+        .expect("expected a suggestion for chain");
+    let description = chain.description.as_deref().unwrap_or_default();
+    assert!(!description.contains("[1 2 3 4 5] | chain 2"));
+}
+
+#[test]
+fn overlay_hide_completions_suggest_active_overlays() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let source = r#"module spam { export def foo [] { "foo" } }
+    overlay use spam"#;
+    assert!(support::merge_input(source.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let completion_str = "overlay hide ".to_string();
+    let suggestions = completer.complete(&completion_str, completion_str.len());
+    assert!(suggestions.iter().any(|s| s.value == "spam"));
+}
+
 #[test]
 #[ignore]
 fn external_completer_trailing_space() {
@@ -215,18 +692,75 @@ fn external_completer_pass_flags() {
 }
 
 #[test]
-fn file_completions() {
-    // Create a new engine
-    let (dir, dir_str, engine, stack) = new_engine();
+fn external_completer_receives_cursor_offset() {
+    // The second positional is the cursor's byte offset within the current
+    // token, so an external completer can tell `gh api --he` (cursor at the
+    // end) apart from `gh api --he|ader` (cursor in the middle).
+    let block = "{|spans, offset| [$offset]}";
+    let input = "gh api --he".to_string();
 
-    // Instantiate a new completer
-    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    let suggestions = run_external_completion(block, &input);
+    assert_eq!(1, suggestions.len());
+    assert_eq!("4", suggestions.first().unwrap().value);
+}
 
-    // Test completions for the current folder
-    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
-    let suggestions = completer.complete(&target_dir, target_dir.len());
+#[test]
+fn external_completer_without_offset_param_still_works() {
+    let block = "{|spans| $spans}";
+    let input = "gh alias".to_string();
 
-    // Create the expected values
+    let suggestions = run_external_completion(block, &input);
+    assert_eq!(2, suggestions.len());
+    assert_eq!("gh", suggestions.first().unwrap().value);
+    assert_eq!("alias", suggestions.get(1).unwrap().value);
+}
+
+#[test]
+fn external_completer_fallback_sentinel_merges_file_completions() {
+    // Returning `{ completions: [...], fallback: true }` instead of a bare
+    // list should keep the external completer's own suggestions and still
+    // append file completions, deduplicated.
+    let block = r#"{|spans| { completions: ["another"], fallback: true } }"#;
+    let input = "gh anot".to_string();
+
+    let suggestions = run_external_completion(block, &input);
+    assert_eq!(2, suggestions.len());
+    assert_eq!("another", suggestions.first().unwrap().value);
+    #[cfg(windows)]
+    assert_eq!("another\\", suggestions.get(1).unwrap().value);
+    #[cfg(not(windows))]
+    assert_eq!("another/", suggestions.get(1).unwrap().value);
+}
+
+#[test]
+fn external_completer_fallback_sentinel_dedupes_matching_file() {
+    // If the external completer's own suggestion already matches a file
+    // completion, the file entry should not be duplicated.
+    #[cfg(windows)]
+    let value = "another\\";
+    #[cfg(not(windows))]
+    let value = "another/";
+    let block = format!(r#"{{|spans| {{ completions: ["{value}"], fallback: true }} }}"#);
+    let input = "gh anot".to_string();
+
+    let suggestions = run_external_completion(&block, &input);
+    assert_eq!(1, suggestions.len());
+    assert_eq!(value, suggestions.first().unwrap().value);
+}
+
+#[test]
+fn file_completions() {
+    // Create a new engine
+    let (dir, dir_str, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Test completions for the current folder
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    // Create the expected values
     let expected_paths: Vec<String> = vec![
         folder(dir.join("another")),
         file(dir.join("custom_completion.nu")),
@@ -262,6 +796,161 @@ fn file_completions() {
     match_suggestions(expected_paths, suggestions);
 }
 
+#[test]
+fn file_completions_dirs_first() {
+    // Off by default: entries are interleaved alphabetically, as in `file_completions`
+    let (dir, dir_str, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        folder(dir.join("another")),
+        file(dir.join("custom_completion.nu")),
+        folder(dir.join("directory_completion")),
+        file(dir.join("nushell")),
+        folder(dir.join("test_a")),
+        folder(dir.join("test_b")),
+        file(dir.join(".hidden_file")),
+        folder(dir.join(".hidden_folder")),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+
+    // On: directories are grouped ahead of files, each group keeping its
+    // own sort order, and hidden entries are still appended last
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.dirs_first = true",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        folder(dir.join("another")),
+        folder(dir.join("directory_completion")),
+        folder(dir.join("test_a")),
+        folder(dir.join("test_b")),
+        file(dir.join("custom_completion.nu")),
+        file(dir.join("nushell")),
+        file(dir.join(".hidden_file")),
+        folder(dir.join(".hidden_folder")),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn open_with_trailing_dot_completes_every_extension_sharing_the_stem() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+    std::fs::write(dir.join("a.nu"), "").unwrap();
+    std::fs::write(dir.join("a.txt"), "").unwrap();
+
+    let (dir, dir_str, engine, stack) = support::new_engine_at(dir);
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = format!("open {dir_str}{MAIN_SEPARATOR}a.");
+    let suggestions = completer.complete(&line, line.len());
+
+    let expected: Vec<String> = vec![file(dir.join("a.nu")), file(dir.join("a.txt"))];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn file_completions_hidden_files_enabled_by_default() {
+    let (dir, dir_str, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join(".hidden_file"))));
+}
+
+#[test]
+fn file_completions_hidden_files_disabled() {
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.hidden_files = false",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Hidden entries are omitted when the typed prefix doesn't start with a dot
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    assert!(!suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join(".hidden_file"))));
+    assert!(!suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join(".hidden_folder"))));
+
+    // But they're still offered once the user starts typing a dot themselves
+    let target_dir = format!("cp {}", file(dir.join(".")));
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join(".hidden_file"))));
+}
+
+#[test]
+fn file_completions_respect_gitignore_only_when_enabled() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+    std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    std::fs::create_dir(dir.join("target")).unwrap();
+    std::fs::create_dir(dir.join("src")).unwrap();
+
+    let (dir, dir_str, engine, stack) = support::new_engine_at(dir);
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Disabled by default: `target` is still suggested
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join("target"))));
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join("src"))));
+
+    let (dir, dir_str, mut engine, mut stack) = support::new_engine_at(dir);
+    assert!(support::merge_input(
+        b"$env.config.completions.use_ignore_files = true",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Enabled: `target` is hidden by the `.gitignore`, `src` is unaffected
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    assert!(!suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join("target"))));
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join("src"))));
+}
+
 #[test]
 fn partial_completions() {
     // Create a new engine
@@ -394,6 +1083,83 @@ fn partial_completions() {
     match_suggestions(expected_paths, suggestions);
 }
 
+#[test]
+fn partial_completions_disabled_requires_exact_interior_directories() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_partial_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.partial = false",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // With partial expansion disabled, `pa` no longer resolves to any of
+    // `partial_a`, `partial_b`, `partial_c` as an interior component, so
+    // completing `pa/h` (a file under one of them) finds nothing.
+    let dir_str = file(dir.join("pa").join("h"));
+    let target_dir = format!("cp {dir_str}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    assert!(suggestions.is_empty());
+
+    // The final component is unaffected -- completing a folder's own name
+    // still expands normally.
+    let target_dir = format!("cd {}", file(dir.join("pa")));
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        folder(dir.join("partial_a")),
+        folder(dir.join("partial_b")),
+        folder(dir.join("partial_c")),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+
+    // An interior component that resolves to an existing directory exactly
+    // still completes within it.
+    let dir_str = file(dir.join("partial_a").join("h"));
+    let target_dir = format!("cp {dir_str}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        file(dir.join("partial_a").join("have_ext.exe")),
+        file(dir.join("partial_a").join("have_ext.txt")),
+        file(dir.join("partial_a").join("hello")),
+        file(dir.join("partial_a").join("hola")),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn partial_completions_are_unaffected_by_directory_read_caching() {
+    // `pa` expands into `partial_a`, `partial_b` and `partial_c`, so completing
+    // `pa/h` reads each of those directories once per candidate expansion.
+    // The per-call directory cache introduced for this should be invisible to
+    // callers: the results must be identical to the uncached case.
+    let (dir, _, engine, stack) = new_partial_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let dir_str = file(dir.join("pa").join("h"));
+    let target_dir = format!("cp {dir_str}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        file(dir.join("partial_a").join("have_ext.exe")),
+        file(dir.join("partial_a").join("have_ext.txt")),
+        file(dir.join("partial_a").join("hello")),
+        file(dir.join("partial_a").join("hola")),
+        file(dir.join("partial_b").join("hello_b")),
+        file(dir.join("partial_b").join("hi_b")),
+        file(dir.join("partial_c").join("hello_c")),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
 #[test]
 fn command_ls_with_filecompletion() {
     let (_, _, engine, stack) = new_engine();
@@ -547,6 +1313,27 @@ fn command_cp_with_globcompletion() {
     match_suggestions(expected_paths, suggestions)
 }
 
+#[test]
+fn command_rm_with_brace_expansion_completion() {
+    let (_, _, engine, stack) = new_engine();
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = "rm {test_a,te";
+    let suggestions = completer.complete(target_dir, target_dir.len());
+
+    #[cfg(windows)]
+    let expected_paths: Vec<String> = vec![
+        "{test_a,test_a\\".to_string(),
+        "{test_a,test_b\\".to_string(),
+    ];
+    #[cfg(not(windows))]
+    let expected_paths: Vec<String> =
+        vec!["{test_a,test_a/".to_string(), "{test_a,test_b/".to_string()];
+
+    match_suggestions(expected_paths, suggestions)
+}
+
 #[test]
 fn command_save_with_filecompletion() {
     let (_, _, engine, stack) = new_engine();
@@ -688,6 +1475,85 @@ fn file_completion_quoted() {
     match_suggestions(expected_paths, suggestions)
 }
 
+#[test]
+fn file_completion_continues_unterminated_double_quote() {
+    let (_, _, engine, stack) = new_quote_engine();
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // The quote is left open on purpose -- completion should close it with
+    // `"` instead of falling back to the usual backtick escaping.
+    let target_dir = "open \"te";
+    let suggestions = completer.complete(target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        "\"te st.txt\"".to_string(),
+        "\"te#st.txt\"".to_string(),
+        "\"te'st.txt\"".to_string(),
+        "\"te(st).txt\"".to_string(),
+        format!("\"{}\"", folder("test dir".into())),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn file_completion_continues_unterminated_raw_string() {
+    let (_, _, engine, stack) = new_quote_engine();
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // The raw string is left open on purpose -- completion should close it
+    // with the same number of `#`s it was opened with instead of falling
+    // back to the usual backtick escaping.
+    let target_dir = "open r#'te";
+    let suggestions = completer.complete(target_dir, target_dir.len());
+
+    let expected_paths: Vec<String> = vec![
+        // `te'st.txt` contains a single quote, which a raw string opened
+        // with one `#` can't express without ending early, so this one
+        // falls back to the usual backtick escaping instead.
+        "`te'st.txt`".to_string(),
+        "r#'te st.txt'#".to_string(),
+        "r#'te#st.txt'#".to_string(),
+        "r#'te(st).txt'#".to_string(),
+        format!("r#'{}'#", folder("test dir".into())),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn flag_completion_ignores_trailing_comment() {
+    // Create a new engine
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    // The cursor sits right after `-`, before the comment; the comment
+    // text shouldn't affect what gets completed there.
+    let suggestions = completer.complete("ls - # list things", 4);
+
+    assert_eq!(16, suggestions.len());
+}
+
+#[test]
+fn flag_completion_stops_after_dash_dash_terminator() {
+    // Create a new engine
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    // Once `--` appears, later `-`-prefixed tokens are positionals (here,
+    // file paths), not flags.
+    let suggestions = completer.complete("ls -- -", 7);
+
+    // No fixture file starts with `-`, so a correct positional/file
+    // completion finds nothing; before the fix, this returned `ls`'s 16
+    // flag suggestions instead.
+    assert!(suggestions.is_empty());
+}
+
 #[test]
 fn flag_completions() {
     // Create a new engine
@@ -724,38 +1590,262 @@ fn flag_completions() {
 }
 
 #[test]
-fn folder_with_directorycompletions() {
+fn flag_completion_short_form_description_mentions_long_form() {
     // Create a new engine
-    let (dir, dir_str, engine, stack) = new_engine();
+    let (_, _, engine, stack) = new_engine();
 
-    // Instantiate a new completer
     let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
-
-    // Test completions for the current folder
-    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}");
-    let suggestions = completer.complete(&target_dir, target_dir.len());
-
-    // Create the expected values
-    let expected_paths: Vec<String> = vec![
-        folder(dir.join("another")),
-        folder(dir.join("directory_completion")),
-        folder(dir.join("test_a")),
-        folder(dir.join("test_b")),
-        folder(dir.join(".hidden_folder")),
-    ];
-
-    // Match the results
-    match_suggestions(expected_paths, suggestions);
+    let suggestions = completer.fetch_completions_at("ls -l", 5);
+
+    let short_l = suggestions
+        .iter()
+        .find(|s| s.suggestion.value == "-l")
+        .expect("expected a suggestion for -l");
+    let description = short_l
+        .suggestion
+        .description
+        .as_ref()
+        .expect("expected -l to have a description");
+    assert!(
+        description.contains("--long"),
+        "expected description of -l to mention --long, got: {description}"
+    );
 }
 
 #[test]
-fn variables_completions() {
+fn flag_completion_continues_a_short_flag_cluster() {
     // Create a new engine
-    let (dir, _, mut engine, mut stack) = new_engine();
-
-    // Add record value as example
-    let record = "let actor = { name: 'Tom Hardy', age: 44 }";
-    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    // With `-a` already typed, completion should offer to extend the
+    // cluster (`-al`, `-ad`, ...) instead of only ever restarting from a
+    // bare `-`.
+    let suggestions = completer.complete("ls -a", 5);
+
+    let values: Vec<String> = suggestions.iter().map(|s| s.value.clone()).collect();
+
+    assert!(
+        values.contains(&"-al".to_string()),
+        "expected -al among {values:?}"
+    );
+    assert!(
+        values.contains(&"-ad".to_string()),
+        "expected -ad among {values:?}"
+    );
+    // The bare `-a` itself is still offered (matching the pre-existing
+    // prefix-completion behavior).
+    assert!(values.contains(&"-a".to_string()));
+}
+
+#[test]
+fn registered_command_completer_overrides_positional_logic() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = "def mycmd [name: string] {}";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    completer.register_command_completer(
+        "mycmd",
+        Arc::new(|_spans| {
+            vec![
+                Suggestion {
+                    value: "db_one".into(),
+                    ..Default::default()
+                },
+                Suggestion {
+                    value: "db_two".into(),
+                    ..Default::default()
+                },
+            ]
+        }),
+    );
+
+    let suggestions = completer.complete("mycmd ", 6);
+    let expected: Vec<String> = vec!["db_one".into(), "db_two".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn complete_streaming_stops_early() {
+    use std::ops::ControlFlow;
+
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let mut seen = vec![];
+    completer.complete_streaming("ls -", 4, |suggestion| {
+        seen.push(suggestion.value);
+        if seen.len() == 2 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert_eq!(2, seen.len());
+
+    let all = completer.complete("ls -", 4);
+    assert!(all.len() > seen.len());
+    assert_eq!(
+        all[..2].iter().map(|s| s.value.clone()).collect::<Vec<_>>(),
+        seen
+    );
+}
+
+#[test]
+fn metrics_sink_receives_non_zero_parse_time() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let metrics: Arc<Mutex<Vec<nu_cli::CompletionMetrics>>> = Arc::new(Mutex::new(vec![]));
+    let sink_metrics = metrics.clone();
+    completer.set_metrics_sink(Some(Arc::new(move |m| {
+        sink_metrics.lock().unwrap().push(m)
+    })));
+
+    completer.complete("ls ", 3);
+
+    let recorded = metrics.lock().unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert!(recorded[0].parse_time.as_nanos() > 0);
+}
+
+#[test]
+fn folder_with_directorycompletions() {
+    // Create a new engine
+    let (dir, dir_str, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Test completions for the current folder
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    // Create the expected values
+    let expected_paths: Vec<String> = vec![
+        folder(dir.join("another")),
+        folder(dir.join("directory_completion")),
+        folder(dir.join("test_a")),
+        folder(dir.join("test_b")),
+        folder(dir.join(".hidden_folder")),
+    ];
+
+    // Match the results
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn directory_completion_does_not_append_whitespace() {
+    // Create a new engine
+    let (dir, dir_str, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // A directory suggestion already ends in a separator, so reedline
+    // shouldn't add a trailing space after accepting it.
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    assert!(!suggestions.is_empty());
+    assert!(suggestions.iter().all(|s| !s.append_whitespace));
+}
+
+#[test]
+fn no_value_flag_completion_appends_whitespace() {
+    // Create a new engine
+    let (_, _, engine, stack) = new_engine();
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `-a` takes no value, so accepting it should leave the cursor ready
+    // for the next argument rather than glued to the flag.
+    let suggestions = completer.complete("ls -a", 5);
+
+    let short_a = suggestions
+        .iter()
+        .find(|s| s.value == "-a")
+        .expect("expected a suggestion for -a");
+
+    assert!(short_a.append_whitespace);
+}
+
+#[test]
+fn cd_completions_recent_directories() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = format!(
+        r#"$env.DIRS_LIST = ["{}", "{}", "{}"]"#,
+        folder(dir.join("test_a")),
+        folder(dir.join("test_b")),
+        folder(dir.join("another")),
+    );
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir.clone()).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Nothing typed yet: recent directories are suggested, most recent first
+    let suggestions = completer.complete("cd ", 3);
+    let expected: Vec<String> = vec![
+        folder(dir.join("another")),
+        folder(dir.join("test_b")),
+        folder(dir.join("test_a")),
+    ];
+    match_suggestions(expected, suggestions);
+
+    // Once a prefix is typed, normal directory completion takes over
+    let target_dir = format!("cd {}", file(dir.join("test")));
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    let expected: Vec<String> = vec![folder(dir.join("test_a")), folder(dir.join("test_b"))];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn folder_with_directorycompletions_append_slash_disabled() {
+    // Create a new engine
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.append_slash = false",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+
+    // Instantiate a new completer
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // Test completions for the current folder
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+
+    // With append_slash disabled, directory suggestions don't carry a trailing separator
+    let expected_paths: Vec<String> = vec![
+        dir.join("another").to_string_lossy().to_string(),
+        dir.join("directory_completion")
+            .to_string_lossy()
+            .to_string(),
+        dir.join("test_a").to_string_lossy().to_string(),
+        dir.join("test_b").to_string_lossy().to_string(),
+        dir.join(".hidden_folder").to_string_lossy().to_string(),
+    ];
+
+    // Match the results
+    match_suggestions(expected_paths, suggestions);
+}
+
+#[test]
+fn variables_completions() {
+    // Create a new engine
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    // Add record value as example
+    let record = "let actor = { name: 'Tom Hardy', age: 44 }";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
 
     // Instantiate a new completer
     let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
@@ -859,6 +1949,267 @@ fn variables_completions() {
     match_suggestions(expected, suggestions);
 }
 
+#[test]
+fn nu_constant_completions_match_exported_keys() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine.clone()), Arc::new(stack));
+
+    let mut expected = nu_protocol::eval_const::nu_constant_keys(&engine);
+    expected.sort();
+
+    let suggestions = completer.complete("$nu.", 4);
+    let mut actual: Vec<String> = suggestions.into_iter().map(|s| s.value).collect();
+    actual.sort();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn env_var_completions_carry_a_description() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.fetch_completions_at("$env.T", 6);
+
+    assert_eq!(1, suggestions.len());
+    let suggestion = &suggestions[0];
+    assert_eq!("TEST", suggestion.suggestion.value);
+    let description = suggestion
+        .suggestion
+        .description
+        .as_ref()
+        .expect("env var suggestion should carry a description");
+    assert!(description.contains("NUSHELL"));
+}
+
+#[test]
+fn env_var_completions_inside_external_call_argument() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("^echo $env.T", 12);
+
+    let expected: Vec<String> = vec!["TEST".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn literal_record_cell_path_completions() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "({a: {b: {c: 1}}}).a.b.";
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(vec!["c".to_string()], suggestions);
+}
+
+#[test]
+fn spread_argument_cell_path_completions() {
+    // `mycmd` has no `...rest` parameter, so `...$actor.` fails to parse and
+    // collapses to a single garbage span -- the completer still needs to
+    // find `$actor`'s cell path underneath the spread.
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let commands = "def mycmd [name: string] {}\n\
+        let actor = { name: 'Tom Hardy', age: 44 }";
+    assert!(support::merge_input(commands.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "mycmd ...$actor.";
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(vec!["name".to_string(), "age".to_string()], suggestions);
+}
+
+#[test]
+fn case_sensitive_paths_overrides_case_sensitive_for_file_completion() {
+    // Globally case-sensitive, but paths explicitly set to case-insensitive:
+    // an upper-cased prefix should still match the lower-case fixture dir.
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.case_sensitive = true; $env.config.completions.case_sensitive_paths = false",
+        &mut engine,
+        &mut stack,
+        dir.clone()
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}TEST_A");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    match_suggestions(vec![folder(dir.join("test_a"))], suggestions);
+
+    // With no override, case_sensitive_paths follows the (here: sensitive) global
+    // setting, so the same upper-cased prefix matches nothing.
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.case_sensitive = true",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}TEST_A");
+    let suggestions = completer.complete(&target_dir, target_dir.len());
+    assert!(suggestions.is_empty());
+}
+
+#[test]
+fn variable_completions_honor_completions_case_sensitive() {
+    // Case-insensitive: an upper-cased prefix still matches `$actor`.
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let commands = "$env.config.completions.case_sensitive = false\n\
+        let actor = { name: 'Tom Hardy', age: 44 }";
+    assert!(support::merge_input(commands.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$AC";
+    let suggestions = completer.complete(line, line.len());
+    match_suggestions(vec!["$actor".to_string()], suggestions);
+
+    // Case-sensitive (the default): the same upper-cased prefix matches nothing.
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let commands = "let actor = { name: 'Tom Hardy', age: 44 }";
+    assert!(support::merge_input(commands.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$AC";
+    let suggestions = completer.complete(line, line.len());
+    assert!(suggestions.is_empty());
+}
+
+#[test]
+fn pipeline_output_columns_completions() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "ls | get ";
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(
+        vec![
+            "modified".to_string(),
+            "name".to_string(),
+            "size".to_string(),
+            "type".to_string(),
+        ],
+        suggestions,
+    );
+}
+
+#[test]
+fn where_row_condition_columns_completions() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "ls | where ";
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(
+        vec![
+            "modified".to_string(),
+            "name".to_string(),
+            "size".to_string(),
+            "type".to_string(),
+        ],
+        suggestions,
+    );
+}
+
+#[test]
+fn config_hooks_completions() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$env.config.hooks.";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec![
+        "command_not_found".into(),
+        "display_output".into(),
+        "env_change".into(),
+        "pre_execution".into(),
+        "pre_prompt".into(),
+    ];
+    match_suggestions(expected, suggestions);
+
+    let line = "$env.config.hooks.pre_";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec!["pre_execution".into(), "pre_prompt".into()];
+    match_suggestions(expected, suggestions);
+
+    let line = "$env.config.hooks.env_change.";
+    let suggestions = completer.complete(line, line.len());
+    #[cfg(windows)]
+    let expected: Vec<String> = vec!["PWD".into(), "Path".into(), "TEST".into()];
+    #[cfg(not(windows))]
+    let expected: Vec<String> = vec!["PATH".into(), "PWD".into(), "TEST".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn config_completions_key_suggestions() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$env.config.completions.";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec![
+        "algorithm".into(),
+        "append_slash".into(),
+        "case_sensitive".into(),
+        "case_sensitive_paths".into(),
+        "dirs_first".into(),
+        "external".into(),
+        "fuzzy_anchor".into(),
+        "hidden_files".into(),
+        "partial".into(),
+        "path_roots".into(),
+        "quick".into(),
+        "show_examples".into(),
+        "sort".into(),
+        "transform".into(),
+        "use_ignore_files".into(),
+        "use_ls_colors".into(),
+    ];
+    match_suggestions(expected, suggestions);
+
+    let line = "$env.config.completions.a";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec!["algorithm".into(), "append_slash".into()];
+    match_suggestions(expected, suggestions);
+
+    let line = "$env.config.completions.external.";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec![
+        "completer".into(),
+        "enable".into(),
+        "max_results".into(),
+        "timeout".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn config_top_level_completion_warns_about_deprecated_key() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$env.config.history_file";
+    let suggestions = completer.complete(line, line.len());
+
+    let deprecated = suggestions
+        .iter()
+        .find(|s| s.value == "history_file_format")
+        .expect("expected the deprecated key to still be suggested");
+    assert_eq!(
+        deprecated.description.as_deref(),
+        Some("deprecated; use history.file_format instead")
+    );
+}
+
 #[test]
 fn alias_of_command_and_flags() {
     let (dir, _, mut engine, mut stack) = new_engine();
@@ -919,14 +2270,44 @@ fn alias_of_another_alias() {
     match_suggestions(expected_paths, suggestions)
 }
 
-fn run_external_completion(completer: &str, input: &str) -> Vec<Suggestion> {
-    let completer = format!("$env.config.completions.external.completer = {completer}");
+#[test]
+fn flag_completion_resolves_through_chained_aliases() {
+    let (dir, _, mut engine, mut stack) = new_engine();
 
-    // Create a new engine
-    let (dir, _, mut engine_state, mut stack) = new_engine();
-    let (block, delta) = {
-        let mut working_set = StateWorkingSet::new(&engine_state);
-        let block = parse(&mut working_set, None, completer.as_bytes(), false);
+    // Create an alias
+    let alias = r#"alias ll = ls -la"#;
+    assert!(support::merge_input(alias.as_bytes(), &mut engine, &mut stack, dir.clone()).is_ok());
+    // Create the second alias
+    let alias = r#"alias lf = ll -f"#;
+    assert!(support::merge_input(alias.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `lf` aliases `ll`, which aliases `ls` -- flag completion should
+    // resolve through both layers down to `ls`'s own long flags.
+    let suggestions = completer.complete("lf --", 5);
+    let expected: Vec<String> = vec![
+        "--all".into(),
+        "--directory".into(),
+        "--du".into(),
+        "--full-paths".into(),
+        "--help".into(),
+        "--long".into(),
+        "--mime-type".into(),
+        "--short-names".into(),
+    ];
+
+    match_suggestions(expected, suggestions)
+}
+
+fn run_external_completion(completer: &str, input: &str) -> Vec<Suggestion> {
+    let completer = format!("$env.config.completions.external.completer = {completer}");
+
+    // Create a new engine
+    let (dir, _, mut engine_state, mut stack) = new_engine();
+    let (block, delta) = {
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        let block = parse(&mut working_set, None, completer.as_bytes(), false);
         assert!(working_set.parse_errors.is_empty());
 
         (block, working_set.render())
@@ -1044,6 +2425,41 @@ fn filecompletions_triggers_after_cursor() {
     match_suggestions(expected_paths, suggestions);
 }
 
+#[test]
+fn filecompletions_triggers_after_leading_whitespace() {
+    let (_, _, engine, stack) = new_engine();
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "   ls ";
+    let suggestions = completer.complete(line, line.len());
+
+    #[cfg(windows)]
+    let expected_paths: Vec<String> = vec![
+        "another\\".to_string(),
+        "custom_completion.nu".to_string(),
+        "directory_completion\\".to_string(),
+        "nushell".to_string(),
+        "test_a\\".to_string(),
+        "test_b\\".to_string(),
+        ".hidden_file".to_string(),
+        ".hidden_folder\\".to_string(),
+    ];
+    #[cfg(not(windows))]
+    let expected_paths: Vec<String> = vec![
+        "another/".to_string(),
+        "custom_completion.nu".to_string(),
+        "directory_completion/".to_string(),
+        "nushell".to_string(),
+        "test_a/".to_string(),
+        "test_b/".to_string(),
+        ".hidden_file".to_string(),
+        ".hidden_folder/".to_string(),
+    ];
+
+    match_suggestions(expected_paths, suggestions);
+}
+
 #[rstest]
 fn extern_custom_completion_positional(mut extern_completer: NuCompleter) {
     let suggestions = extern_completer.complete("spam ", 5);
@@ -1114,6 +2530,127 @@ fn custom_completer_triggers_cursor_after_word(mut custom_completer: NuCompleter
     match_suggestions(expected, suggestions);
 }
 
+#[test]
+fn external_completer_timeout_falls_back_to_file_completion() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    // A completer that hangs well past its configured timeout.
+    let record = r#"
+        let external_completer = {|spans| sleep 10sec; [] }
+
+        $env.config.completions.external = {
+            enable: true
+            max_results: 100
+            completer: $external_completer
+            timeout: 10ms
+        }
+    "#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    let start = std::time::Instant::now();
+    let suggestions = completer.complete("cmd foo bar ", 12);
+
+    // The external completer never got to answer, so this should have
+    // fallen back to file completions well before the closure's
+    // 10-second sleep finishes.
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // Falls back to plain file completions for the fixtures directory.
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == "custom_completion.nu"));
+}
+
+#[cfg(unix)]
+#[test]
+fn external_command_completes_path_binaries_and_filesystem_paths() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+
+    let binary_path = dir.join("stub_binary");
+    std::fs::write(&binary_path, "#!/bin/sh\n").unwrap();
+    let mut perm = std::fs::metadata(&binary_path).unwrap().permissions();
+    perm.set_mode(0o755);
+    std::fs::set_permissions(&binary_path, perm).unwrap();
+
+    let (dir, dir_str, mut engine, mut stack) = support::new_engine_at(dir);
+    stack.add_env_var(
+        "PATH".to_string(),
+        nu_protocol::Value::string(dir_str.clone(), nu_protocol::Span::test_data()),
+    );
+    assert!(engine.merge_env(&mut stack, &dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // A bare external name completes from `$env.PATH` binaries.
+    let line = "^stub";
+    let suggestions = completer.complete(line, line.len());
+    assert!(suggestions.iter().any(|s| s.value == "stub_binary"));
+
+    // A path-separator-containing external name can never match a PATH
+    // binary name, so it completes from the filesystem instead.
+    let line = format!("^{dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&line, line.len());
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join("stub_binary"))));
+}
+
+#[test]
+fn path_filter_excludes_entries_outside_the_allowed_root() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+
+    let allowed = dir.join("allowed");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::write(allowed.join("inside.txt"), "").unwrap();
+    std::fs::write(dir.join("sibling.txt"), "").unwrap();
+
+    let (dir, dir_str, engine, stack) = support::new_engine_at(dir);
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let allowed = dir.join("allowed");
+    completer.set_path_filter(Some(Arc::new(move |path: &std::path::Path| {
+        path == allowed || path.starts_with(&allowed)
+    })));
+
+    let line = format!("open {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == folder(dir.join("allowed"))));
+    assert!(!suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join("sibling.txt"))));
+
+    let line = format!("open {dir_str}{MAIN_SEPARATOR}allowed{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&line, line.len());
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == file(dir.join("allowed").join("inside.txt"))));
+}
+
+#[test]
+fn path_root_completes_relative_to_mapped_base_dir_instead_of_cwd() {
+    let (dir, _, engine, stack) = new_engine();
+    let base_dir = dir
+        .join("test_a")
+        .into_os_string()
+        .into_string()
+        .expect("valid utf8 path");
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    completer.register_path_root("mycmd", base_dir);
+
+    // `myfile` only exists under `test_a`, not directly in the fixtures
+    // directory that's actually the session's cwd, so this only succeeds if
+    // the mapped base directory (not cwd) was used to resolve completions.
+    let suggestions = completer.complete("mycmd ", 6);
+
+    assert!(suggestions.iter().any(|s| s.value == "myfile"));
+}
+
 #[ignore = "was reverted, still needs fixing"]
 #[rstest]
 fn alias_offset_bug_7648() {
@@ -1160,3 +2697,538 @@ fn get_path_env_var_8003() {
     // Make sure it's not empty
     assert!(the_path.is_some());
 }
+
+#[test]
+fn config_completions_algorithm_value_completes() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "$env.config.completions.algorithm = ";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec!["fuzzy".into(), "prefix".into(), "substring".into()];
+    match_suggestions(expected, suggestions);
+
+    let line = "$env.config.completions.algorithm = fu";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec!["fuzzy".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn fuzzy_completions_expose_match_score() {
+    // Create a new engine
+    let (dir, dir_str, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.algorithm = fuzzy",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // "te" is a tight, contiguous match for "test_a"/"test_b" but only a
+    // scattered subsequence match for "another".
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}te");
+    let suggestions = completer.fetch_completions_at(&target_dir, target_dir.len());
+
+    let tight_match = suggestions
+        .iter()
+        .find(|s| {
+            s.suggestion
+                .value
+                .ends_with(&format!("test_a{MAIN_SEPARATOR}"))
+        })
+        .expect("test_a should fuzzy-match \"te\"");
+    let loose_match = suggestions
+        .iter()
+        .find(|s| {
+            s.suggestion
+                .value
+                .ends_with(&format!("another{MAIN_SEPARATOR}"))
+        })
+        .expect("another should fuzzy-match \"te\"");
+
+    let tight_score = tight_match
+        .match_score
+        .expect("fuzzy match should be scored");
+    let loose_score = loose_match
+        .match_score
+        .expect("fuzzy match should be scored");
+    assert!(
+        tight_score > loose_score,
+        "expected a tighter match to score higher: {tight_score} <= {loose_score}"
+    );
+
+    // Non-fuzzy algorithms don't compute a score.
+    let (_, dir_str, engine, stack) = new_engine();
+    let mut prefix_completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}te");
+    let suggestions = prefix_completer.fetch_completions_at(&target_dir, target_dir.len());
+    assert!(suggestions.iter().all(|s| s.match_score.is_none()));
+}
+
+#[test]
+fn fuzzy_completions_ignore_accents() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+    std::fs::create_dir(dir.join("café")).unwrap();
+
+    let (dir, dir_str, mut engine, mut stack) = support::new_engine_at(dir);
+    assert!(support::merge_input(
+        b"$env.config.completions.algorithm = fuzzy",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // The accented directory should fuzzy-match the unaccented "cafe", since
+    // matching normalizes Unicode -- the suggestion itself is still returned
+    // with its real, accented name.
+    let target_dir = format!("cp {dir_str}{MAIN_SEPARATOR}cafe");
+    let suggestions = completer.fetch_completions_at(&target_dir, target_dir.len());
+
+    assert!(suggestions.iter().any(|s| s
+        .suggestion
+        .value
+        .ends_with(&format!("café{MAIN_SEPARATOR}"))));
+}
+
+#[test]
+fn fuzzy_anchor_start_requires_the_token_to_match_the_beginning_of_the_word() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+    std::fs::create_dir(dir.join("cd-project")).unwrap();
+    std::fs::create_dir(dir.join("src-cd")).unwrap();
+
+    let (dir, dir_str, mut engine, mut stack) = support::new_engine_at(dir);
+    assert!(support::merge_input(
+        b"$env.config.completions.algorithm = fuzzy
+          $env.config.completions.fuzzy_anchor = start",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let target_dir = format!("cd {dir_str}{MAIN_SEPARATOR}cd");
+    let suggestions = completer.fetch_completions_at(&target_dir, target_dir.len());
+    let values: Vec<&String> = suggestions.iter().map(|s| &s.suggestion.value).collect();
+
+    assert!(values
+        .iter()
+        .any(|v| v.ends_with(&format!("cd-project{MAIN_SEPARATOR}"))));
+    assert!(!values
+        .iter()
+        .any(|v| v.ends_with(&format!("src-cd{MAIN_SEPARATOR}"))));
+}
+
+#[test]
+fn alias_of_command_propagates_subcommand_completion() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def "foo bar" [] { "hi" }
+    alias f = foo"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("f ba", 4);
+    let expected: Vec<String> = vec!["foo bar".to_string()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn subcommand_completion_wins_over_a_same_named_directory_entry() {
+    // The fixtures dir already has `test_a` and `test_b` directories, which
+    // share the `test` prefix being completed below; `foo test` should win
+    // outright rather than being merged in alongside them.
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def "foo test" [] { "hi" }"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("foo te", 6);
+    let expected: Vec<String> = vec!["foo test".to_string()];
+    match_suggestions(expected, suggestions);
+}
+
+#[rstest]
+fn def_custom_completion_long_flag(mut def_completer: NuCompleter) {
+    let suggestions = def_completer.complete("spam --foo=", 11);
+    let expected: Vec<String> = vec!["cat".into(), "dog".into(), "eel".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[rstest]
+fn def_bool_switch_flag_value(mut def_completer: NuCompleter) {
+    let suggestions = def_completer.complete("spam --enabled=", 15);
+    let expected: Vec<String> = vec!["false".into(), "true".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn duration_typed_argument_completes_unit_suffixes() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def take-a-nap [howlong: duration] { }"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "take-a-nap 5";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec![
+        "5day".into(),
+        "5hr".into(),
+        "5min".into(),
+        "5ms".into(),
+        "5ns".into(),
+        "5sec".into(),
+        "5us".into(),
+        "5wk".into(),
+        "5\u{b5}s".into(),
+        "5\u{3bc}s".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn filesize_typed_argument_completes_unit_suffixes() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def alloc [amount: filesize] { }"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "alloc 5k";
+    let suggestions = completer.complete(line, line.len());
+    let expected: Vec<String> = vec!["5kb".into(), "5kib".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn record_typed_argument_completes_declared_field_names() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def greet [info: record<name: string, age: int>] { }"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "greet { ";
+    let suggestions = completer.complete(line, line.len());
+
+    let expected: Vec<String> = vec!["age".into(), "name".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn record_typed_argument_excludes_already_set_field_names() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+
+    let record = r#"def greet [info: record<name: string, age: int>] { }"#;
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "greet { name: \"Bob\", ";
+    let suggestions = completer.complete(line, line.len());
+
+    let expected: Vec<String> = vec!["age".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn variable_completion_includes_the_enclosing_closure_parameter() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "{|spans| $sp";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "spans"));
+}
+
+#[test]
+fn operator_completions_after_an_int_literal_include_arithmetic_operators() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "3 ";
+    let suggestions = completer.complete(line, line.len());
+
+    let expected: Vec<String> = vec![
+        "!=".into(),
+        "*".into(),
+        "**".into(),
+        "+".into(),
+        "-".into(),
+        "/".into(),
+        "<".into(),
+        "<=".into(),
+        "==".into(),
+        ">".into(),
+        ">=".into(),
+        "fdiv".into(),
+        "in".into(),
+        "mod".into(),
+        "not-in".into(),
+    ];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn match_pattern_completions_for_bool_subject() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "match true { }";
+    let suggestions = completer.complete(line, line.len() - 1);
+
+    let expected: Vec<String> = vec!["false".into(), "true".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn command_name_completion_inside_interpolation() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = r#"$"hello (l"#;
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "ls"));
+}
+
+#[test]
+fn pipeline_output_columns_completions_inside_interpolation() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = r#"$"hello (ls | get na"#;
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(vec!["name".to_string()], suggestions);
+}
+
+#[test]
+fn pipeline_output_columns_completions_inside_parenthesized_argument() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "print (ls | get na";
+    let suggestions = completer.complete(line, line.len());
+
+    match_suggestions(vec!["name".to_string()], suggestions);
+}
+
+#[rstest]
+fn def_custom_completion_long_flag_space(mut def_completer: NuCompleter) {
+    let suggestions = def_completer.complete("spam --foo ", 11);
+    let expected: Vec<String> = vec!["cat".into(), "dog".into(), "eel".into()];
+    match_suggestions(expected, suggestions);
+}
+
+#[test]
+fn completions_transform_uppercases_file_completions() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    assert!(support::merge_input(
+        b"$env.config.completions.transform = {|suggestion| $suggestion | str upcase }",
+        &mut engine,
+        &mut stack,
+        dir
+    )
+    .is_ok());
+
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let suggestions = completer.complete("nushel", 6);
+    let expected: Vec<String> = vec!["NUSHELL".into()];
+    match_suggestions(expected, suggestions);
+}
+
+// This version of nushell has no `@category`/`@example`/`@search-terms`
+// attribute syntax for `def` (the lexer and parser don't treat a leading
+// `@` specially at all), so there's no attribute-argument completer to
+// extend yet. This pins the current fallback: `@category ` is parsed as an
+// ordinary, unknown command name and falls through to plain path/file
+// completion of the cwd.
+#[test]
+fn attribute_like_prefix_falls_back_to_file_completion() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "@category ";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "test_a/"));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_to_directory_completes_as_directory() {
+    let temp = tempfile::tempdir().expect("failed to create temp dir");
+    let dir = temp.path().to_path_buf();
+    std::fs::create_dir(dir.join("real_dir")).unwrap();
+    std::os::unix::fs::symlink(dir.join("real_dir"), dir.join("linked_dir")).unwrap();
+
+    let (dir, dir_str, engine, stack) = support::new_engine_at(dir);
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = format!("cd {dir_str}{MAIN_SEPARATOR}");
+    let suggestions = completer.complete(&line, line.len());
+
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value == folder(dir.join("linked_dir"))));
+}
+
+#[test]
+fn cd_dash_completes_to_oldpwd_when_set() {
+    let (_, dir_str, engine, mut stack) = new_engine();
+    stack.add_env_var(
+        "OLDPWD".to_string(),
+        nu_protocol::Value::string(dir_str, nu_protocol::Span::test_data()),
+    );
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "cd -";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "-"));
+}
+
+#[test]
+fn cd_dash_not_suggested_without_oldpwd() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "cd -";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(!suggestions.iter().any(|s| s.value == "-"));
+}
+
+#[test]
+fn command_completion_includes_description() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "ls";
+    let suggestions = completer.complete(line, line.len());
+
+    let ls = suggestions
+        .iter()
+        .find(|s| s.value == "ls")
+        .expect("expected `ls` to be suggested");
+    assert!(ls.description.is_some());
+}
+
+#[test]
+fn command_completion_matches_by_search_term() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // `ls` declares "dir" as one of its search terms.
+    let line = "di";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "ls"));
+}
+
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+#[test]
+fn cd_tilde_username_completes_to_matching_accounts() {
+    let (_, _, engine, stack) = new_engine();
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    // "root" always exists as a local account on Unix.
+    let line = "cd ~roo";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions
+        .iter()
+        .any(|s| s.value.trim_end_matches(MAIN_SEPARATOR) == "~root"));
+}
+
+#[test]
+fn path_typed_arg_completes_files() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = "def foo [x: path] { }";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "foo n";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.iter().any(|s| s.value == "nushell"));
+}
+
+#[test]
+fn string_typed_arg_does_not_complete_files() {
+    let (dir, _, mut engine, mut stack) = new_engine();
+    let record = "def foo [x: string] { }";
+    assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+    let mut completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+    let line = "foo n";
+    let suggestions = completer.complete(line, line.len());
+
+    assert!(suggestions.is_empty());
+}
+
+#[cfg(feature = "async")]
+mod complete_async {
+    use super::*;
+    use std::{
+        future::Future,
+        task::{Context, Poll, Wake},
+        thread::Thread,
+    };
+
+    /// A minimal, dependency-free `block_on`: parks the current thread between
+    /// polls and relies on [`ThreadWaker`] to unpark it once the background
+    /// completion thread has a result, rather than busy-polling.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(value) => return value,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn complete_async_resolves_to_the_same_suggestions_as_complete() {
+        let (dir, _, mut engine, mut stack) = new_engine();
+        let record = "def foo [x: path] { }";
+        assert!(support::merge_input(record.as_bytes(), &mut engine, &mut stack, dir).is_ok());
+        let completer = NuCompleter::new(Arc::new(engine), Arc::new(stack));
+
+        let line = "foo n";
+        let suggestions = block_on(completer.complete_async(line, line.len()));
+
+        assert!(suggestions.iter().any(|s| s.value == "nushell"));
+    }
+}
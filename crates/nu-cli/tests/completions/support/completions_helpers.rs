@@ -68,6 +68,31 @@ pub fn new_engine() -> (PathBuf, String, EngineState, Stack) {
     (dir, dir_str, engine_state, stack)
 }
 
+// creates a new engine with the current path set to an arbitrary directory,
+// for tests that need a directory they fully control (e.g. one containing a
+// `.gitignore`) rather than the shared completions fixtures.
+pub fn new_engine_at(dir: PathBuf) -> (PathBuf, String, EngineState, Stack) {
+    let dir_str = dir
+        .clone()
+        .into_os_string()
+        .into_string()
+        .unwrap_or_default();
+
+    let mut engine_state = create_default_context();
+    engine_state.generate_nu_constant();
+
+    let mut stack = Stack::new();
+    stack.add_env_var(
+        "PWD".to_string(),
+        Value::string(dir_str.clone(), nu_protocol::Span::new(0, dir_str.len())),
+    );
+
+    let merge_result = engine_state.merge_env(&mut stack, &dir);
+    assert!(merge_result.is_ok());
+
+    (dir, dir_str, engine_state, stack)
+}
+
 pub fn new_quote_engine() -> (PathBuf, String, EngineState, Stack) {
     // Target folder inside assets
     let dir = fs::fixtures().join("quoted_completions");
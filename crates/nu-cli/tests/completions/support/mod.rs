@@ -1,3 +1,5 @@
 pub mod completions_helpers;
 
-pub use completions_helpers::{file, folder, match_suggestions, merge_input, new_engine};
+pub use completions_helpers::{
+    file, folder, match_suggestions, merge_input, new_engine, new_engine_at,
+};
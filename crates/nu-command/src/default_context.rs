@@ -31,6 +31,8 @@ pub fn add_shell_command_context(mut engine_state: EngineState) -> EngineState {
             All,
             Any,
             Append,
+            Chain,
+            Chunks,
             Columns,
             Compact,
             Default,
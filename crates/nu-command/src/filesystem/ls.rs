@@ -49,7 +49,18 @@ impl Command for Ls {
 
     fn signature(&self) -> nu_protocol::Signature {
         Signature::build("ls")
-            .input_output_types(vec![(Type::Nothing, Type::table())])
+            .input_output_types(vec![(
+                Type::Nothing,
+                Type::Table(
+                    vec![
+                        ("name".to_string(), Type::String),
+                        ("type".to_string(), Type::Any),
+                        ("size".to_string(), Type::Any),
+                        ("modified".to_string(), Type::Any),
+                    ]
+                    .into_boxed_slice(),
+                ),
+            )])
             // LsGlobPattern is similar to string, it won't auto-expand
             // and we use it to track if the user input is quoted.
             .rest("pattern", SyntaxShape::OneOf(vec![SyntaxShape::GlobPattern, SyntaxShape::String]), "The glob pattern to use.")
@@ -0,0 +1,1113 @@
+use nu_engine::{command_prelude::*, ClosureEvalOnce};
+use nu_protocol::{engine::Closure, PipelineIterator, ValueIterator};
+
+#[derive(Clone)]
+pub struct Chain;
+
+impl Command for Chain {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("chain")
+            .input_output_types(vec![
+                (
+                    Type::List(Box::new(Type::Any)),
+                    Type::List(Box::new(Type::List(Box::new(Type::Any)))),
+                ),
+                (
+                    Type::List(Box::new(Type::Binary)),
+                    Type::List(Box::new(Type::Binary)),
+                ),
+                (
+                    Type::Range,
+                    Type::List(Box::new(Type::List(Box::new(Type::Any)))),
+                ),
+            ])
+            .required("chunk_size", SyntaxShape::Int, "The size of each chunk.")
+            .rest(
+                "iterables",
+                SyntaxShape::Closure(None),
+                "Additional closures whose output is appended after the input (and after each \
+                    other) before chunking. Each closure is only evaluated once the iterable \
+                    before it has been fully consumed, so an early-terminating chunk consumer \
+                    can avoid running later closures at all.",
+            )
+            .named(
+                "from",
+                SyntaxShape::Int,
+                "Start index (inclusive) of the chunks to keep. Negative counts from the end.",
+                None,
+            )
+            .named(
+                "to",
+                SyntaxShape::Int,
+                "End index (exclusive) of the chunks to keep. Negative counts from the end.",
+                None,
+            )
+            .named(
+                "skip",
+                SyntaxShape::Int,
+                "Drop this many of the leading closures from `iterables` before evaluating any \
+                    of them, so their output never enters the chain at all.",
+                None,
+            )
+            .named(
+                "take",
+                SyntaxShape::Int,
+                "Keep only this many closures from `iterables`, counted after any `--skip`.",
+                None,
+            )
+            .named(
+                "depth",
+                SyntaxShape::Int,
+                "How many levels of nested lists to flatten while building each chunk \
+                    (default 1, i.e. simple concatenation that keeps inner lists intact). \
+                    Each level beyond that splices one more layer of nested lists directly \
+                    into the chunk.",
+                Some('d'),
+            )
+            .switch(
+                "unwrap-errors",
+                "turn a failing element into an `{error: ...}` record instead of letting the error propagate",
+                None,
+            )
+            .switch(
+                "ignore-errors",
+                "drop a failing element (warning on stderr) instead of letting the error propagate",
+                Some('e'),
+            )
+            .switch(
+                "interleave",
+                "round-robin between the input and each closure's output instead of \
+                    concatenating them, dropping an iterable once it's exhausted and \
+                    continuing to round-robin the rest",
+                Some('i'),
+            )
+            .switch(
+                "collect",
+                "eagerly buffer all chunks into a single list instead of streaming them, \
+                    so e.g. `length` sees an exact count immediately",
+                Some('c'),
+            )
+            .switch(
+                "strict",
+                "require every combined element to be a list before chunking; if any \
+                    aren't, fail with a single error listing all of them (not just the \
+                    first), so scripts built programmatically can fix every offender at \
+                    once instead of one round-trip at a time",
+                None,
+            )
+            .category(Category::Filters)
+    }
+
+    fn usage(&self) -> &str {
+        "Splits input into fixed-size chunks."
+    }
+
+    fn extra_usage(&self) -> &str {
+        "Drops any pipeline metadata (such as `ls`'s data source or an opened file's path) from \
+            the input, since the output may combine it with unrelated closure output and the \
+            metadata would no longer describe the result. A chunk made up entirely of binary \
+            values is concatenated into a single binary value instead of a list; mixing binary \
+            with other types in the same chunk is an error."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        let stream_test_1 = vec![
+            Value::list(
+                vec![Value::test_int(1), Value::test_int(2)],
+                Span::test_data(),
+            ),
+            Value::list(
+                vec![Value::test_int(3), Value::test_int(4)],
+                Span::test_data(),
+            ),
+            Value::list(vec![Value::test_int(5)], Span::test_data()),
+        ];
+
+        vec![
+            Example {
+                example: "[1 2 3 4 5] | chain 2",
+                description: "Chunk the input into groups of 2, with a final remainder chunk",
+                result: Some(Value::list(stream_test_1, Span::test_data())),
+            },
+            Example {
+                example: "[1 2 3] | chain --interleave 1 { [10 20 30] }",
+                description: "Round-robin the input with each closure's output instead of \
+                    appending them in sequence",
+                result: Some(Value::list(
+                    vec![
+                        Value::list(vec![Value::test_int(1)], Span::test_data()),
+                        Value::list(vec![Value::test_int(10)], Span::test_data()),
+                        Value::list(vec![Value::test_int(2)], Span::test_data()),
+                        Value::list(vec![Value::test_int(20)], Span::test_data()),
+                        Value::list(vec![Value::test_int(3)], Span::test_data()),
+                        Value::list(vec![Value::test_int(30)], Span::test_data()),
+                    ],
+                    Span::test_data(),
+                )),
+            },
+            Example {
+                example: "[1 2 3] | chain --collect 2",
+                description: "Buffer the chunks into a single list instead of streaming them, \
+                    e.g. so `length` sees an exact count immediately",
+                result: Some(Value::list(
+                    vec![
+                        Value::list(
+                            vec![Value::test_int(1), Value::test_int(2)],
+                            Span::test_data(),
+                        ),
+                        Value::list(vec![Value::test_int(3)], Span::test_data()),
+                    ],
+                    Span::test_data(),
+                )),
+            },
+            Example {
+                example: "[(0x[01 02]) (0x[03 04])] | chain 999999999",
+                description: "A chunk made up entirely of binary values is concatenated into \
+                    one binary value instead of a list",
+                result: Some(Value::list(
+                    vec![Value::binary(vec![1, 2, 3, 4], Span::test_data())],
+                    Span::test_data(),
+                )),
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let head = call.head;
+        let chunk_size: Spanned<i64> = call.req(engine_state, stack, 0)?;
+        let mut iterables: Vec<Closure> = call.rest(engine_state, stack, 1)?;
+        let from: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "from")?;
+        let to: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "to")?;
+        let skip: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "skip")?;
+        let take: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "take")?;
+        let depth: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "depth")?;
+        let unwrap_errors = call.has_flag(engine_state, stack, "unwrap-errors")?;
+        let ignore_errors = call.has_flag(engine_state, stack, "ignore-errors")?;
+        let interleave = call.has_flag(engine_state, stack, "interleave")?;
+        let collect = call.has_flag(engine_state, stack, "collect")?;
+        let strict = call.has_flag(engine_state, stack, "strict")?;
+        let ctrlc = engine_state.ctrlc.clone();
+
+        if chunk_size.item == 0 {
+            return Err(ShellError::IncorrectValue {
+                msg: "chunk size cannot be zero".into(),
+                val_span: chunk_size.span,
+                call_span: head,
+            });
+        }
+        if chunk_size.item < 0 {
+            return Err(ShellError::NeedsPositiveValue {
+                span: chunk_size.span,
+            });
+        }
+        if let Some(skip) = &skip {
+            if skip.item < 0 {
+                return Err(ShellError::NeedsPositiveValue { span: skip.span });
+            }
+        }
+        if let Some(take) = &take {
+            if take.item < 0 {
+                return Err(ShellError::NeedsPositiveValue { span: take.span });
+            }
+        }
+        if let Some(depth) = &depth {
+            if depth.item < 1 {
+                return Err(ShellError::NeedsPositiveValue { span: depth.span });
+            }
+        }
+        let depth = depth.map(|d| d.item as usize).unwrap_or(1);
+
+        // `input.into_iter()` (and, below, the `PipelineData` produced by each
+        // closure) turns a bare `Value::Range` into a `ListStream` driven by
+        // the range's own `into_range_iter`, so descending ranges (`5..1`),
+        // stepped ranges (`1..2..9`), and unbounded ranges (`10..`) all
+        // flatten with their real semantics and without eagerly collecting.
+
+        // One span per closure argument, so a closure that fails outright (or
+        // whose output ends up wrapped by `--unwrap-errors`) can be blamed on
+        // its own position in the `chain` call instead of the call as a
+        // whole.
+        let mut iterable_spans: Vec<Span> = call.rest_iter(1).map(|(expr, _)| expr.span).collect();
+
+        // `--skip`/`--take` trim the *closures themselves* out of `iterables`
+        // before any of them run, unlike `--from`/`--to` which slice the
+        // already-produced chunks: a skipped closure's side effects never
+        // happen at all, not just its output being discarded.
+        let skip_count = (skip.map(|s| s.item as usize).unwrap_or(0)).min(iterables.len());
+        iterables.drain(0..skip_count);
+        iterable_spans.drain(0..skip_count);
+        if let Some(take) = take {
+            let take_count = (take.item as usize).min(iterables.len());
+            iterables.truncate(take_count);
+            iterable_spans.truncate(take_count);
+        }
+
+        let combined_input: ValueIterator = if interleave {
+            // Round-robining requires all the sub-iterators to be live at
+            // once, so unlike the sequential case, each closure has to be
+            // evaluated up front rather than on demand.
+            let mut iters: Vec<ValueIterator> = vec![Box::new(input.into_iter())];
+            for closure in iterables {
+                let data = ClosureEvalOnce::new(engine_state, stack, closure)
+                    .run_with_input(PipelineData::Empty)?;
+                iters.push(Box::new(data.into_iter()));
+            }
+            Box::new(RoundRobinChain::new(iters))
+        } else {
+            let extra_iterables = LazyClosureChain::new(
+                engine_state.clone(),
+                stack.clone(),
+                iterables,
+                iterable_spans,
+                head,
+            );
+            Box::new(input.into_iter().chain(extra_iterables))
+        };
+
+        let combined_input: ValueIterator = if strict {
+            Box::new(reject_non_lists(combined_input, head)?.into_iter())
+        } else {
+            combined_input
+        };
+
+        let chain_iterator = ChainInter::new(
+            chunk_size.item as usize,
+            combined_input,
+            head,
+            engine_state.clone(),
+            ctrlc.clone(),
+        )
+        .with_unwrap_errors(unwrap_errors)
+        .with_ignore_errors(ignore_errors)
+        .with_depth(depth);
+
+        let sliced = slice_chain(
+            Box::new(chain_iterator),
+            from.map(|f| f.item),
+            to.map(|t| t.item),
+        );
+
+        // `chain` may merge the input with unrelated closure output, so the
+        // input's metadata (e.g. `ls`'s data source, or the path of an opened
+        // file) no longer describes the result and is dropped rather than
+        // carried through, which could otherwise mislead something like
+        // `save` into treating the merged stream as the original file.
+        if collect {
+            Ok(PipelineData::Value(
+                Value::list(sliced.collect(), head),
+                None,
+            ))
+        } else {
+            Ok(sliced.into_pipeline_data(head, ctrlc))
+        }
+    }
+}
+
+/// Concatenates the output of a list of closures onto whatever iterable comes
+/// before it, evaluating each closure only once the previous one is
+/// exhausted. This lets `chain 2 { first-batch } { expensive-batch }` avoid
+/// running `expensive-batch` at all if the consumer stops early.
+struct LazyClosureChain {
+    engine_state: EngineState,
+    stack: Stack,
+    pending: std::vec::IntoIter<(Closure, Span)>,
+    current: Option<PipelineIterator>,
+}
+
+impl LazyClosureChain {
+    /// `fallback_span` is used for any closure past the end of `spans` (e.g.
+    /// more closures were spread in via `...` than `chain` captured spans
+    /// for), so every closure still gets *some* span to blame instead of a
+    /// missing one.
+    fn new(
+        engine_state: EngineState,
+        stack: Stack,
+        closures: Vec<Closure>,
+        spans: Vec<Span>,
+        fallback_span: Span,
+    ) -> Self {
+        let pending = closures
+            .into_iter()
+            .zip(spans.into_iter().chain(std::iter::repeat(fallback_span)))
+            .collect::<Vec<_>>();
+
+        Self {
+            engine_state,
+            stack,
+            pending: pending.into_iter(),
+            current: None,
+        }
+    }
+}
+
+impl Iterator for LazyClosureChain {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = &mut self.current {
+                if let Some(value) = current.next() {
+                    return Some(value);
+                }
+                self.current = None;
+            }
+
+            if nu_utils::ctrl_c::was_pressed(&self.engine_state.ctrlc) {
+                return None;
+            }
+
+            let (closure, closure_span) = self.pending.next()?;
+            match ClosureEvalOnce::new(&self.engine_state, &self.stack, closure)
+                .run_with_input(PipelineData::Empty)
+            {
+                Ok(data) => self.current = Some(data.into_iter()),
+                // The closure itself failed to run at all (as opposed to one
+                // of its output values later being rejected downstream), so
+                // blame its own position in the `chain` call rather than the
+                // call as a whole.
+                Err(err) => return Some(Value::error(err, closure_span)),
+            }
+        }
+    }
+}
+
+/// Alternates between a fixed set of iterators, taking one value from each in
+/// turn and dropping an iterator as soon as it's exhausted so the rest keep
+/// round-robining without it.
+struct RoundRobinChain {
+    iters: std::collections::VecDeque<ValueIterator>,
+}
+
+impl RoundRobinChain {
+    fn new(iters: impl IntoIterator<Item = ValueIterator>) -> Self {
+        Self {
+            iters: iters.into_iter().collect(),
+        }
+    }
+}
+
+impl Iterator for RoundRobinChain {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(mut iter) = self.iters.pop_front() {
+            if let Some(value) = iter.next() {
+                self.iters.push_back(iter);
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+pub(crate) struct ChainInter {
+    pub(crate) chunk_size: usize,
+    pub(crate) input: ValueIterator,
+    pub(crate) span: Span,
+    pub(crate) unwrap_errors: bool,
+    pub(crate) ignore_errors: bool,
+    /// How many levels of nested lists a chunk is flattened to before being
+    /// turned into its final value; 1 (the default) leaves a chunk exactly
+    /// as grouped.
+    pub(crate) depth: usize,
+    pub(crate) engine_state: EngineState,
+    pub(crate) ctrlc: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// Set once a Ctrl-C interrupt has been surfaced, so `next()` returns
+    /// `None` afterward instead of yielding the same error forever -- callers
+    /// that `.collect()` this iterator (`chain --collect`, negative
+    /// `--from`/`--to` slicing) would otherwise hang.
+    pub(crate) done: bool,
+}
+
+impl ChainInter {
+    /// Groups `input` into fixed-size chunks, with none of `chain`'s extra
+    /// behavior (error handling, flattening) turned on. This is what
+    /// `chunks` -- a plain grouping command with no other flags -- uses; the
+    /// `with_*` builders below are for `chain`-only behavior, so a new
+    /// `chain`-only field doesn't require touching `chunks.rs` at all.
+    pub(crate) fn new(
+        chunk_size: usize,
+        input: ValueIterator,
+        span: Span,
+        engine_state: EngineState,
+        ctrlc: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    ) -> Self {
+        Self {
+            chunk_size,
+            input,
+            span,
+            unwrap_errors: false,
+            ignore_errors: false,
+            depth: 1,
+            engine_state,
+            ctrlc,
+            done: false,
+        }
+    }
+
+    pub(crate) fn with_unwrap_errors(mut self, unwrap_errors: bool) -> Self {
+        self.unwrap_errors = unwrap_errors;
+        self
+    }
+
+    pub(crate) fn with_ignore_errors(mut self, ignore_errors: bool) -> Self {
+        self.ignore_errors = ignore_errors;
+        self
+    }
+
+    pub(crate) fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+}
+
+impl Iterator for ChainInter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut group = vec![];
+
+        loop {
+            if nu_utils::ctrl_c::was_pressed(&self.ctrlc) {
+                self.done = true;
+                return Some(Value::error(
+                    ShellError::InterruptedByUser {
+                        span: Some(self.span),
+                    },
+                    self.span,
+                ));
+            }
+
+            match self.input.next() {
+                Some(Value::Error { error, .. }) if self.ignore_errors => {
+                    nu_protocol::report_error_new(&self.engine_state, &*error);
+                    continue;
+                }
+                Some(v) => {
+                    group.push(if self.unwrap_errors {
+                        unwrap_error_value(v)
+                    } else {
+                        v
+                    });
+
+                    if group.len() >= self.chunk_size {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        if group.is_empty() {
+            return None;
+        }
+
+        let group = flatten_group(group, self.depth.saturating_sub(1));
+
+        Some(group_to_value(group, self.span))
+    }
+}
+
+/// Splices any `Value::List` in `group` into its own elements, in place,
+/// `levels` times -- e.g. with `levels == 1`, `[[1 2] 3]` becomes `[1 2 3]`.
+/// This is what `--depth` controls: depth 1 (the default) passes `levels ==
+/// 0` so a chunk is left exactly as grouped, and each depth beyond that
+/// flattens one more layer of nested lists.
+fn flatten_group(group: Vec<Value>, levels: usize) -> Vec<Value> {
+    let mut group = group;
+    for _ in 0..levels {
+        group = group
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::List { vals, .. } => vals,
+                other => vec![other],
+            })
+            .collect();
+    }
+    group
+}
+
+/// Turns a chunk's elements into the `Value` that represents it: a
+/// concatenated `Value::Binary` when every element is binary (so e.g.
+/// `chain 999999999 (open --raw a.bin) (open --raw b.bin) | save out.bin`
+/// produces one continuous byte stream instead of a list of binary blobs),
+/// otherwise the usual `Value::List`. A chunk mixing binary and non-binary
+/// elements is a clear user error rather than silently falling back to a
+/// list.
+fn group_to_value(group: Vec<Value>, span: Span) -> Value {
+    let binary_count = group
+        .iter()
+        .filter(|v| matches!(v, Value::Binary { .. }))
+        .count();
+
+    if binary_count == 0 {
+        return Value::list(group, span);
+    }
+
+    if binary_count != group.len() {
+        return Value::error(
+            ShellError::GenericError {
+                error: "chain combined binary and non-binary elements".into(),
+                msg: "this chunk mixes binary data with other value types".into(),
+                span: Some(span),
+                help: Some(
+                    "make sure every element being chained together in the same chunk is binary"
+                        .into(),
+                ),
+                inner: vec![],
+            },
+            span,
+        );
+    }
+
+    let bytes: Vec<u8> = group
+        .into_iter()
+        .flat_map(|v| match v {
+            Value::Binary { val, .. } => val,
+            _ => unreachable!("checked above that every element is binary"),
+        })
+        .collect();
+    Value::binary(bytes, span)
+}
+
+/// Slices an iterator of chunks down to the `[from, to)` range, where `to` is
+/// exclusive and either bound may be negative to count from the end.
+/// Non-negative bounds are applied lazily via `skip`/`take`; a negative bound
+/// requires collecting the chunks first to know how far back it points.
+fn slice_chain(
+    input: ValueIterator,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Box<dyn Iterator<Item = Value> + Send> {
+    let needs_buffering = matches!(from, Some(f) if f < 0) || matches!(to, Some(t) if t < 0);
+
+    if !needs_buffering {
+        let skip = from.unwrap_or(0) as usize;
+        let iter = input.skip(skip);
+        match to {
+            Some(to) => Box::new(iter.take((to as usize).saturating_sub(skip))),
+            None => Box::new(iter),
+        }
+    } else {
+        let chunks: Vec<Value> = input.collect();
+        let len = chunks.len() as i64;
+        let resolve = |idx: i64| -> usize {
+            if idx < 0 {
+                (len + idx).max(0) as usize
+            } else {
+                (idx as usize).min(chunks.len())
+            }
+        };
+
+        let start = from.map(resolve).unwrap_or(0);
+        let end = to.map(resolve).unwrap_or(chunks.len()).max(start);
+
+        Box::new(chunks.into_iter().skip(start).take(end - start))
+    }
+}
+
+/// Consumes `input` fully and, in `--strict` mode, checks that every element
+/// is a list. An upstream `Value::Error` is left for `--ignore-errors` /
+/// `--unwrap-errors` to deal with downstream rather than being flagged here.
+///
+/// Unlike a plain `?` on the first bad element, this reports every offender
+/// at once: scripts assembling `chain --strict` calls programmatically can
+/// fix every mistake in one round-trip instead of discovering them one at a
+/// time.
+fn reject_non_lists(input: ValueIterator, span: Span) -> Result<Vec<Value>, ShellError> {
+    let values: Vec<Value> = input.collect();
+
+    let offenders: Vec<ShellError> = values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| !matches!(value, Value::List { .. } | Value::Error { .. }))
+        .map(|(index, value)| ShellError::GenericError {
+            error: format!("element {index} is a {}, not a list", value.get_type()),
+            msg: format!("element {index} is a {}", value.get_type()),
+            span: Some(value.span()),
+            help: None,
+            inner: vec![],
+        })
+        .collect();
+
+    if offenders.is_empty() {
+        return Ok(values);
+    }
+
+    let help = offenders
+        .iter()
+        .map(|err| match err {
+            ShellError::GenericError { msg, span, .. } => format!(
+                "{msg} (span {}..{})",
+                span.map(|s| s.start).unwrap_or_default(),
+                span.map(|s| s.end).unwrap_or_default()
+            ),
+            _ => unreachable!("only GenericError is built above"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Err(ShellError::GenericError {
+        error: format!(
+            "chain --strict requires every element to be a list, but {} weren't",
+            offenders.len()
+        ),
+        msg: "combined with elements that aren't lists".into(),
+        span: Some(span),
+        help: Some(help),
+        inner: offenders,
+    })
+}
+
+/// Converts an error value produced upstream into an `{error: ...}` record
+/// so a failing element doesn't abort the rest of the chain. The record is
+/// tagged with the error value's own span (rather than `chain`'s call span)
+/// so a downstream command that rejects it still points back at whichever
+/// element produced it.
+fn unwrap_error_value(value: Value) -> Value {
+    let span = value.span();
+    match value {
+        Value::Error { error, .. } => Value::record(
+            record! {
+                "error" => Value::string(error.to_string(), span),
+            },
+            span,
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        use crate::test_examples;
+
+        test_examples(Chain {})
+    }
+
+    #[test]
+    fn unwrap_errors_converts_error_value_to_record() {
+        let span = Span::test_data();
+        let value = Value::error(ShellError::NeedsPositiveValue { span }, span);
+
+        let converted = unwrap_error_value(value);
+
+        assert!(matches!(converted, Value::Record { .. }));
+        assert!(converted.as_record().unwrap().get("error").is_some());
+    }
+
+    #[test]
+    fn unwrap_errors_tags_record_with_the_error_values_own_span() {
+        let head = Span::new(0, 3);
+        let element_span = Span::new(20, 40);
+        let value = Value::error(ShellError::NeedsPositiveValue { span: head }, element_span);
+
+        let converted = unwrap_error_value(value);
+
+        assert_eq!(converted.span(), element_span);
+    }
+
+    #[test]
+    fn without_flag_error_value_passes_through_unchanged() {
+        let span = Span::test_data();
+        let value = Value::error(ShellError::NeedsPositiveValue { span }, span);
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(vec![value.clone(), Value::test_int(1)].into_iter()),
+            span,
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert!(matches!(vals[0], Value::Error { .. }));
+        assert_eq!(vals[1], Value::test_int(1));
+    }
+
+    #[test]
+    fn signaled_interrupt_yields_interrupted_by_user_error() {
+        let span = Span::test_data();
+        let ctrlc = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(vec![Value::test_int(1), Value::test_int(2)].into_iter()),
+            span,
+            EngineState::new(),
+            Some(ctrlc),
+        );
+
+        let Some(Value::Error { error, .. }) = chain.next() else {
+            panic!("expected an interrupt error");
+        };
+
+        assert!(matches!(*error, ShellError::InterruptedByUser { .. }));
+        assert!(
+            chain.next().is_none(),
+            "should stop after one interrupt error instead of yielding it forever"
+        );
+    }
+
+    #[test]
+    fn round_robin_chain_alternates_equal_length_iterators() {
+        let iters: Vec<ValueIterator> = vec![
+            Box::new(vec![Value::test_int(1), Value::test_int(2)].into_iter()),
+            Box::new(vec![Value::test_string("a"), Value::test_string("b")].into_iter()),
+        ];
+
+        let result: Vec<Value> = RoundRobinChain::new(iters).collect();
+
+        assert_eq!(
+            result,
+            vec![
+                Value::test_int(1),
+                Value::test_string("a"),
+                Value::test_int(2),
+                Value::test_string("b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_robin_chain_drops_exhausted_iterators_and_continues() {
+        let iters: Vec<ValueIterator> = vec![
+            Box::new(vec![Value::test_int(1)].into_iter()),
+            Box::new(vec![Value::test_string("a"), Value::test_string("b")].into_iter()),
+        ];
+
+        let result: Vec<Value> = RoundRobinChain::new(iters).collect();
+
+        assert_eq!(
+            result,
+            vec![
+                Value::test_int(1),
+                Value::test_string("a"),
+                Value::test_string("b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn slice_chain_applies_positive_range_lazily() {
+        let chunks = (0..5).map(Value::test_int);
+
+        let sliced: Vec<Value> = slice_chain(Box::new(chunks), Some(1), Some(3)).collect();
+
+        assert_eq!(sliced, vec![Value::test_int(1), Value::test_int(2)]);
+    }
+
+    #[test]
+    fn slice_chain_applies_negative_to_by_buffering() {
+        let chunks = (0..5).map(Value::test_int);
+
+        let sliced: Vec<Value> = slice_chain(Box::new(chunks), Some(1), Some(-1)).collect();
+
+        assert_eq!(
+            sliced,
+            vec![Value::test_int(1), Value::test_int(2), Value::test_int(3)]
+        );
+    }
+
+    #[test]
+    fn with_flag_iteration_continues_past_error() {
+        let span = Span::test_data();
+        let value = Value::error(ShellError::NeedsPositiveValue { span }, span);
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(vec![value, Value::test_int(1)].into_iter()),
+            span,
+            EngineState::new(),
+            None,
+        )
+        .with_unwrap_errors(true);
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert!(matches!(vals[0], Value::Record { .. }));
+        assert_eq!(vals[1], Value::test_int(1));
+        assert!(chain.next().is_none());
+    }
+
+    #[test]
+    fn chain_inter_chunks_a_descending_range() {
+        let input: PipelineData = Value::test_range(nu_protocol::Range::IntRange(
+            nu_protocol::IntRange::new(
+                Value::test_int(5),
+                Value::nothing(Span::test_data()),
+                Value::test_int(1),
+                nu_protocol::ast::RangeInclusion::Inclusive,
+                Span::test_data(),
+            )
+            .expect("valid range"),
+        ))
+        .into_pipeline_data();
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(input.into_iter()),
+            Span::test_data(),
+            EngineState::new(),
+            None,
+        );
+
+        let chunks: Vec<Value> = std::iter::from_fn(|| chain.next()).collect();
+
+        assert_eq!(
+            chunks,
+            vec![
+                Value::test_list(vec![Value::test_int(5), Value::test_int(4)]),
+                Value::test_list(vec![Value::test_int(3), Value::test_int(2)]),
+                Value::test_list(vec![Value::test_int(1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn chain_inter_chunks_a_stepped_range() {
+        let input: PipelineData = Value::test_range(nu_protocol::Range::IntRange(
+            nu_protocol::IntRange::new(
+                Value::test_int(1),
+                Value::test_int(3),
+                Value::test_int(9),
+                nu_protocol::ast::RangeInclusion::Inclusive,
+                Span::test_data(),
+            )
+            .expect("valid range"),
+        ))
+        .into_pipeline_data();
+
+        let mut chain = ChainInter::new(
+            5,
+            Box::new(input.into_iter()),
+            Span::test_data(),
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert_eq!(
+            vals,
+            vec![
+                Value::test_int(1),
+                Value::test_int(3),
+                Value::test_int(5),
+                Value::test_int(7),
+                Value::test_int(9),
+            ]
+        );
+        assert!(chain.next().is_none());
+    }
+
+    #[test]
+    fn chain_inter_chunks_an_unbounded_range_lazily() {
+        let input: PipelineData = Value::test_range(nu_protocol::Range::IntRange(
+            nu_protocol::IntRange::new(
+                Value::test_int(10),
+                Value::nothing(Span::test_data()),
+                Value::nothing(Span::test_data()),
+                nu_protocol::ast::RangeInclusion::Inclusive,
+                Span::test_data(),
+            )
+            .expect("valid range"),
+        ))
+        .into_pipeline_data();
+
+        let mut chain = ChainInter::new(
+            3,
+            Box::new(input.into_iter()),
+            Span::test_data(),
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert_eq!(
+            vals,
+            vec![
+                Value::test_int(10),
+                Value::test_int(11),
+                Value::test_int(12),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_mode_reports_every_non_list_element_at_once() {
+        let bad_one = Value::test_int(1);
+        let bad_two = Value::test_string("nope");
+        let input: ValueIterator = Box::new(vec![bad_one.clone(), bad_two.clone()].into_iter());
+
+        let Err(ShellError::GenericError { inner, .. }) =
+            reject_non_lists(input, Span::test_data())
+        else {
+            panic!("expected a GenericError aggregating both offenders");
+        };
+
+        let spans: Vec<Span> = inner
+            .iter()
+            .map(|err| match err {
+                ShellError::GenericError { span, .. } => span.expect("offender has a span"),
+                other => panic!("expected a GenericError offender, got {other:?}"),
+            })
+            .collect();
+
+        assert_eq!(spans, vec![bad_one.span(), bad_two.span()]);
+    }
+
+    #[test]
+    fn chain_inter_concatenates_a_chunk_of_all_binary_elements() {
+        let span = Span::test_data();
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(
+                vec![
+                    Value::binary(vec![1, 2], span),
+                    Value::binary(vec![3, 4], span),
+                ]
+                .into_iter(),
+            ),
+            span,
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::Binary { val, .. }) = chain.next() else {
+            panic!("expected a concatenated binary chunk");
+        };
+
+        assert_eq!(val, vec![1, 2, 3, 4]);
+        assert!(chain.next().is_none());
+    }
+
+    #[test]
+    fn chain_inter_errors_on_a_chunk_mixing_binary_and_non_binary_elements() {
+        let span = Span::test_data();
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(vec![Value::binary(vec![1, 2], span), Value::test_int(3)].into_iter()),
+            span,
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::Error { .. }) = chain.next() else {
+            panic!("expected an error value for the mixed chunk");
+        };
+    }
+
+    #[test]
+    fn ignore_errors_drops_failing_value_and_continues() {
+        let span = Span::test_data();
+        let value = Value::error(ShellError::NeedsPositiveValue { span }, span);
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(vec![value, Value::test_int(1), Value::test_int(2)].into_iter()),
+            span,
+            EngineState::new(),
+            None,
+        )
+        .with_ignore_errors(true);
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert_eq!(vals, vec![Value::test_int(1), Value::test_int(2)]);
+        assert!(chain.next().is_none());
+    }
+
+    #[test]
+    fn default_depth_keeps_inner_lists_intact() {
+        let span = Span::test_data();
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(
+                vec![
+                    Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                    Value::test_list(vec![Value::test_int(3)]),
+                ]
+                .into_iter(),
+            ),
+            span,
+            EngineState::new(),
+            None,
+        );
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert_eq!(
+            vals,
+            vec![
+                Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                Value::test_list(vec![Value::test_int(3)]),
+            ]
+        );
+        assert!(chain.next().is_none());
+    }
+
+    #[test]
+    fn depth_two_flattens_one_more_level_of_nested_lists() {
+        let span = Span::test_data();
+
+        let mut chain = ChainInter::new(
+            2,
+            Box::new(
+                vec![
+                    Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                    Value::test_list(vec![Value::test_int(3)]),
+                ]
+                .into_iter(),
+            ),
+            span,
+            EngineState::new(),
+            None,
+        )
+        .with_depth(2);
+
+        let Some(Value::List { vals, .. }) = chain.next() else {
+            panic!("expected a chunk");
+        };
+
+        assert_eq!(
+            vals,
+            vec![Value::test_int(1), Value::test_int(2), Value::test_int(3)]
+        );
+        assert!(chain.next().is_none());
+    }
+}
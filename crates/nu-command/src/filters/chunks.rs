@@ -0,0 +1,93 @@
+use super::chain::ChainInter;
+use nu_engine::command_prelude::*;
+
+#[derive(Clone)]
+pub struct Chunks;
+
+impl Command for Chunks {
+    fn name(&self) -> &str {
+        "chunks"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("chunks")
+            .input_output_types(vec![(
+                Type::List(Box::new(Type::Any)),
+                Type::List(Box::new(Type::List(Box::new(Type::Any)))),
+            )])
+            .required("chunk_size", SyntaxShape::Int, "The size of each chunk.")
+            .category(Category::Filters)
+    }
+
+    fn usage(&self) -> &str {
+        "Splits input into fixed-size chunks."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        let stream_test_1 = vec![
+            Value::list(
+                vec![Value::test_int(1), Value::test_int(2)],
+                Span::test_data(),
+            ),
+            Value::list(
+                vec![Value::test_int(3), Value::test_int(4)],
+                Span::test_data(),
+            ),
+            Value::list(vec![Value::test_int(5)], Span::test_data()),
+        ];
+
+        vec![Example {
+            example: "[1 2 3 4 5] | chunks 2",
+            description: "Chunk the input into groups of 2, with a final remainder chunk",
+            result: Some(Value::list(stream_test_1, Span::test_data())),
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let head = call.head;
+        let chunk_size: Spanned<i64> = call.req(engine_state, stack, 0)?;
+        let ctrlc = engine_state.ctrlc.clone();
+        let metadata = input.metadata();
+
+        if chunk_size.item == 0 {
+            return Err(ShellError::IncorrectValue {
+                msg: "chunk size cannot be zero".into(),
+                val_span: chunk_size.span,
+                call_span: head,
+            });
+        }
+        if chunk_size.item < 0 {
+            return Err(ShellError::NeedsPositiveValue {
+                span: chunk_size.span,
+            });
+        }
+
+        let chunks_iterator = ChainInter::new(
+            chunk_size.item as usize,
+            Box::new(input.into_iter()),
+            head,
+            engine_state.clone(),
+            ctrlc.clone(),
+        );
+
+        Ok(chunks_iterator.into_pipeline_data_with_metadata(head, ctrlc, metadata))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        use crate::test_examples;
+
+        test_examples(Chunks {})
+    }
+}
@@ -1,6 +1,8 @@
 mod all;
 mod any;
 mod append;
+mod chain;
+mod chunks;
 mod columns;
 mod compact;
 mod default;
@@ -58,6 +60,8 @@ mod zip;
 pub use all::All;
 pub use any::Any;
 pub use append::Append;
+pub use chain::Chain;
+pub use chunks::Chunks;
 pub use columns::Columns;
 pub use compact::Compact;
 pub use default::Default;
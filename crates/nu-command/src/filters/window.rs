@@ -11,10 +11,13 @@ impl Command for Window {
 
     fn signature(&self) -> Signature {
         Signature::build("window")
-            .input_output_types(vec![(
-                Type::List(Box::new(Type::Any)),
-                Type::List(Box::new(Type::List(Box::new(Type::Any)))),
-            )])
+            .input_output_types(vec![
+                (Type::List(Box::new(Type::Any)), Type::table()),
+                (
+                    Type::List(Box::new(Type::Any)),
+                    Type::List(Box::new(Type::List(Box::new(Type::Any)))),
+                ),
+            ])
             .required("window_size", SyntaxShape::Int, "The size of each window.")
             .named(
                 "stride",
@@ -27,6 +30,11 @@ impl Command for Window {
                 "yield last chunks even if they have fewer elements than size",
                 Some('r'),
             )
+            .switch(
+                "collect-into-table",
+                "emit a table where each window becomes a row, with auto-named columns and short trailing windows padded with null",
+                None,
+            )
             .category(Category::Filters)
     }
 
@@ -101,6 +109,24 @@ impl Command for Window {
                     Span::test_data(),
                 )),
             },
+            Example {
+                example: "[1, 2, 3, 4] | window 2 --collect-into-table",
+                description: "A sliding window collected into a table with auto-named columns",
+                result: Some(Value::test_list(vec![
+                    Value::test_record(record! {
+                        "column0" => Value::test_int(1),
+                        "column1" => Value::test_int(2),
+                    }),
+                    Value::test_record(record! {
+                        "column0" => Value::test_int(2),
+                        "column1" => Value::test_int(3),
+                    }),
+                    Value::test_record(record! {
+                        "column0" => Value::test_int(3),
+                        "column1" => Value::test_int(4),
+                    }),
+                ])),
+            },
         ]
     }
 
@@ -117,13 +143,15 @@ impl Command for Window {
         let metadata = input.metadata();
         let stride: Option<usize> = call.get_flag(engine_state, stack, "stride")?;
         let remainder = call.has_flag(engine_state, stack, "remainder")?;
+        let collect_into_table = call.has_flag(engine_state, stack, "collect-into-table")?;
 
         let stride = stride.unwrap_or(1);
 
         //FIXME: add in support for external redirection when engine-q supports it generally
 
+        let window_size = group_size.item;
         let each_group_iterator = EachWindowIterator {
-            group_size: group_size.item,
+            group_size: window_size,
             input: Box::new(input.into_iter()),
             span: head,
             previous: None,
@@ -131,10 +159,32 @@ impl Command for Window {
             remainder,
         };
 
-        Ok(each_group_iterator.into_pipeline_data_with_metadata(head, ctrlc, metadata))
+        if collect_into_table {
+            let table_iterator =
+                each_group_iterator.map(move |window| window_to_row(window, window_size, head));
+
+            Ok(table_iterator.into_pipeline_data_with_metadata(head, ctrlc, metadata))
+        } else {
+            Ok(each_group_iterator.into_pipeline_data_with_metadata(head, ctrlc, metadata))
+        }
     }
 }
 
+/// Turns a single window (a `Value::list`) into a record row with auto-named
+/// columns (`column0`, `column1`, ...), padding a short trailing window with null.
+fn window_to_row(window: Value, window_size: usize, span: Span) -> Value {
+    let Value::List { vals, .. } = window else {
+        return window;
+    };
+
+    let columns = (0..window_size).map(|i| format!("column{i}"));
+    let values = vals
+        .into_iter()
+        .chain(std::iter::repeat(Value::nothing(span)));
+
+    Value::record(columns.zip(values).collect(), span)
+}
+
 struct EachWindowIterator {
     group_size: usize,
     input: ValueIterator,
@@ -232,4 +282,41 @@ mod test {
 
         test_examples(Window {})
     }
+
+    #[test]
+    fn window_to_row_names_columns_in_order() {
+        let span = Span::test_data();
+        let window = Value::list(
+            vec![Value::test_int(1), Value::test_int(2), Value::test_int(3)],
+            span,
+        );
+
+        let row = window_to_row(window, 3, span);
+
+        assert_eq!(
+            row,
+            Value::test_record(record! {
+                "column0" => Value::test_int(1),
+                "column1" => Value::test_int(2),
+                "column2" => Value::test_int(3),
+            })
+        );
+    }
+
+    #[test]
+    fn window_to_row_pads_short_trailing_window_with_null() {
+        let span = Span::test_data();
+        let window = Value::list(vec![Value::test_int(4), Value::test_int(5)], span);
+
+        let row = window_to_row(window, 3, span);
+
+        assert_eq!(
+            row,
+            Value::test_record(record! {
+                "column0" => Value::test_int(4),
+                "column1" => Value::test_int(5),
+                "column2" => Value::test_nothing(),
+            })
+        );
+    }
 }
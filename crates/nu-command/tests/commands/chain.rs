@@ -0,0 +1,174 @@
+use nu_test_support::nu;
+
+#[test]
+fn chain_chunks_a_list() {
+    let actual = nu!("[1 2 3 4 5] | chain 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[1,2],[3,4],[5]]");
+}
+
+#[test]
+fn chain_describes_as_list_of_lists() {
+    let actual = nu!("[1 2 3 4 5] | chain 2 | describe");
+
+    assert_eq!(actual.out, "list<list<int>> (stream)");
+}
+
+#[test]
+fn chain_from_to_slices_positive_range() {
+    let actual = nu!("[1 2 3 4 5 6 7 8] | chain 2 --from 1 --to 3 | to json --raw");
+
+    assert_eq!(actual.out, "[[3,4],[5,6]]");
+}
+
+#[test]
+fn chain_negative_to_slices_from_the_end() {
+    let actual = nu!("[1 2 3 4 5 6 7 8] | chain 2 --from 1 --to -1 | to json --raw");
+
+    assert_eq!(actual.out, "[[3,4],[5,6]]");
+}
+
+#[test]
+fn chain_appends_closure_output_after_input() {
+    let actual = nu!("[1 2] | chain 1 { [3 4] } | to json --raw");
+
+    assert_eq!(actual.out, "[[1],[2],[3],[4]]");
+}
+
+#[test]
+fn chain_drops_input_metadata() {
+    let actual = nu!("ls | chain 1 | metadata | get -i source | describe");
+
+    assert_eq!(actual.out, "nothing");
+}
+
+#[test]
+fn chain_does_not_evaluate_later_closures_when_consumer_stops_early() {
+    let actual =
+        nu!("chain 1 { [1 2 3] } { error make {msg: 'should not run'} } | first 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[1],[2]]");
+    assert!(actual.err.is_empty());
+}
+
+#[test]
+fn chain_interleave_round_robins_equal_length_iterables() {
+    let actual = nu!("chain --interleave 1 { [1 2 3] } { [a b c] } | to json --raw");
+
+    assert_eq!(actual.out, r#"[[1],["a"],[2],["b"],[3],["c"]]"#);
+}
+
+#[test]
+fn chain_interleave_drops_exhausted_iterables_and_continues() {
+    let actual = nu!("chain -i 1 { [1] } { [a b c] } | to json --raw");
+
+    assert_eq!(actual.out, r#"[[1],["a"],["b"],["c"]]"#);
+}
+
+#[test]
+fn chain_flattens_a_descending_range() {
+    let actual = nu!("5..1 | chain 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[5,4],[3,2],[1]]");
+}
+
+#[test]
+fn chain_flattens_a_stepped_range() {
+    let actual = nu!("1..3..9 | chain 5 | to json --raw");
+
+    assert_eq!(actual.out, "[[1,3,5,7,9]]");
+}
+
+#[test]
+fn chain_flattens_an_unbounded_range_lazily() {
+    let actual = nu!("10.. | chain 1 | first 3 | to json --raw");
+
+    assert_eq!(actual.out, "[[10],[11],[12]]");
+}
+
+#[test]
+fn chain_collect_describes_as_list_not_stream() {
+    let actual = nu!("[1 2 3] | chain --collect 2 | describe");
+
+    assert_eq!(actual.out, "list<list<int>>");
+}
+
+#[test]
+fn chain_collect_yields_same_chunks_as_streaming() {
+    let actual = nu!("[1 2 3 4 5] | chain --collect 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[1,2],[3,4],[5]]");
+}
+
+#[test]
+fn chain_ignore_errors_skips_failing_closure_and_continues() {
+    let actual = nu!(
+        "chain 1 --ignore-errors { [1 2] } { error make {msg: 'boom'} } { [3 4] } | to json --raw"
+    );
+
+    assert_eq!(actual.out, "[[1],[2],[3],[4]]");
+    assert!(!actual.err.is_empty());
+}
+
+#[test]
+fn chain_unwrap_errors_tags_record_with_the_failing_element_span() {
+    let second_element = "{ error make {msg: 'boom'} }";
+    let script =
+        format!("chain 1 --unwrap-errors {{ [1] }} {second_element} | get 1 | get 0 | metadata | get span | to json --raw");
+
+    let start = script
+        .find(second_element)
+        .expect("second element is present in the script");
+    let end = start + second_element.len();
+
+    let actual = nu!(script);
+
+    assert_eq!(actual.out, format!(r#"{{"start":{start},"end":{end}}}"#));
+}
+
+#[test]
+fn chain_skip_drops_leading_closures_before_they_run() {
+    let actual = nu!(
+        "chain 1 --skip 1 { [1 2] } { [3 4] } { [5 6] } | to json --raw"
+    );
+
+    assert_eq!(actual.out, "[[3],[4],[5],[6]]");
+}
+
+#[test]
+fn chain_take_keeps_only_the_first_closures() {
+    let actual = nu!("chain 1 --take 1 { [1 2] } { [3 4] } { [5 6] } | to json --raw");
+
+    assert_eq!(actual.out, "[[1],[2]]");
+}
+
+#[test]
+fn chain_skip_and_take_combine_to_select_a_middle_range() {
+    let actual = nu!(
+        "chain 1 --skip 1 --take 1 { [1 2] } { [3 4] } { [5 6] } | to json --raw"
+    );
+
+    assert_eq!(actual.out, "[[3],[4]]");
+}
+
+#[test]
+fn chain_skip_never_runs_the_dropped_closure() {
+    let actual = nu!("chain 1 --skip 1 { error make {msg: 'should not run'} } { [1] } | to json --raw");
+
+    assert_eq!(actual.out, "[[1]]");
+    assert!(actual.err.is_empty());
+}
+
+#[test]
+fn chain_skip_past_the_end_yields_input_only() {
+    let actual = nu!("[1 2] | chain 1 --skip 5 { [3 4] } | to json --raw");
+
+    assert_eq!(actual.out, "[[1],[2]]");
+}
+
+#[test]
+fn chain_take_past_the_end_keeps_every_closure() {
+    let actual = nu!("chain 1 --take 99 { [1] } { [2] } | to json --raw");
+
+    assert_eq!(actual.out, "[[1],[2]]");
+}
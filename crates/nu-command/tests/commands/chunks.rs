@@ -0,0 +1,36 @@
+use nu_test_support::nu;
+
+#[test]
+fn chunks_a_list() {
+    let actual = nu!("[1 2 3 4 5] | chunks 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[1,2],[3,4],[5]]");
+}
+
+#[test]
+fn chunks_describes_as_list_of_lists() {
+    let actual = nu!("[1 2 3 4 5] | chunks 2 | describe");
+
+    assert_eq!(actual.out, "list<list<int>> (stream)");
+}
+
+#[test]
+fn chunks_exact_multiple_has_no_remainder_chunk() {
+    let actual = nu!("[1 2 3 4] | chunks 2 | to json --raw");
+
+    assert_eq!(actual.out, "[[1,2],[3,4]]");
+}
+
+#[test]
+fn chunks_zero_size_errors() {
+    let actual = nu!("[1 2 3] | chunks 0 | to json --raw");
+
+    assert!(actual.err.contains("chunk size cannot be zero"));
+}
+
+#[test]
+fn chunks_negative_size_errors() {
+    let actual = nu!("[1 2 3] | chunks -1 | to json --raw");
+
+    assert!(!actual.err.is_empty());
+}
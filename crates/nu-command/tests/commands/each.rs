@@ -28,6 +28,20 @@ fn each_window_stride() {
     assert_eq!(actual.out, "[[1,2,3],[3,4,5]]");
 }
 
+#[test]
+fn each_window_describes_as_list_of_lists() {
+    let actual = nu!("[1 2 3 4] | window 2 | describe");
+
+    assert_eq!(actual.out, "list<list<int>> (stream)");
+}
+
+#[test]
+fn each_window_collect_into_table_describes_as_table() {
+    let actual = nu!("[1 2 3 4] | window 2 --collect-into-table | describe");
+
+    assert_eq!(actual.out, "table<column0: int, column1: int> (stream)");
+}
+
 #[test]
 fn each_no_args_in_block() {
     let actual = nu!("echo [[foo bar]; [a b] [c d] [e f]] | each {|i| $i | to json -r } | get 1");
@@ -7,6 +7,8 @@ mod break_;
 mod bytes;
 mod cal;
 mod cd;
+mod chain;
+mod chunks;
 mod compact;
 mod complete;
 mod config_env_default;
@@ -12,5 +12,5 @@ pub use components::components;
 pub use expansions::{canonicalize_with, expand_path_with, expand_to_real_path, locate_in_dirs};
 pub use helpers::{cache_dir, config_dir, data_dir, get_canonicalized_path, home_dir};
 pub use path::*;
-pub use tilde::expand_tilde;
+pub use tilde::{expand_tilde, users_with_prefix};
 pub use trailing_slash::{has_trailing_slash, strip_trailing_slash};
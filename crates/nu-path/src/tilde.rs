@@ -148,6 +148,24 @@ pub fn expand_tilde(path: impl AsRef<Path>) -> PathBuf {
     expand_tilde_with_home(path, dirs_next::home_dir())
 }
 
+/// List the names of local user accounts whose name starts with `prefix`, for
+/// completing "~user" style paths. Only supported where the `pwd` crate can
+/// enumerate `/etc/passwd`; returns an empty list on platforms (Windows,
+/// macOS, Android) where [`user_home_dir`] instead falls back to
+/// `dirs_next::home_dir`.
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+pub fn users_with_prefix(prefix: &str) -> Vec<String> {
+    Passwd::iter()
+        .map(|passwd| passwd.name)
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+#[cfg(any(target_os = "android", target_os = "windows", target_os = "macos"))]
+pub fn users_with_prefix(_prefix: &str) -> Vec<String> {
+    Vec::new()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
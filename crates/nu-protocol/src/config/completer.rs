@@ -11,6 +11,7 @@ pub enum CompletionAlgorithm {
     #[default]
     Prefix,
     Fuzzy,
+    Substring,
 }
 
 impl FromStr for CompletionAlgorithm {
@@ -20,7 +21,8 @@ impl FromStr for CompletionAlgorithm {
         match s.to_ascii_lowercase().as_str() {
             "prefix" => Ok(Self::Prefix),
             "fuzzy" => Ok(Self::Fuzzy),
-            _ => Err("expected either 'prefix' or 'fuzzy'"),
+            "substring" => Ok(Self::Substring),
+            _ => Err("expected either 'prefix', 'fuzzy' or 'substring'"),
         }
     }
 }
@@ -30,6 +32,79 @@ impl ReconstructVal for CompletionAlgorithm {
         let str = match self {
             CompletionAlgorithm::Prefix => "prefix",
             CompletionAlgorithm::Fuzzy => "fuzzy",
+            CompletionAlgorithm::Substring => "substring",
+        };
+        Value::string(str, span)
+    }
+}
+
+/// Controls which part of a candidate the fuzzy match algorithm requires the
+/// typed text to line up with, independent of `completions.algorithm` (which
+/// must be `"fuzzy"` for this to have any effect).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FuzzyAnchor {
+    /// The typed characters may match anywhere in the candidate, in order
+    /// (the historical behavior), e.g. "gco" matching "git checkout".
+    #[default]
+    Anywhere,
+    /// The first typed character must match the candidate's first
+    /// character, cutting down on noisy matches in long lists, e.g. "cd"
+    /// matching "cd-project" but not "src-cd".
+    Start,
+}
+
+impl FromStr for FuzzyAnchor {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "anywhere" => Ok(Self::Anywhere),
+            "start" => Ok(Self::Start),
+            _ => Err("expected either 'anywhere' or 'start'"),
+        }
+    }
+}
+
+impl ReconstructVal for FuzzyAnchor {
+    fn reconstruct_value(&self, span: Span) -> Value {
+        let str = match self {
+            FuzzyAnchor::Anywhere => "anywhere",
+            FuzzyAnchor::Start => "start",
+        };
+        Value::string(str, span)
+    }
+}
+
+/// Controls the order suggestions are presented in, independent of
+/// `completions.algorithm` (which controls which suggestions match at all).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompletionSort {
+    /// Alphabetical order (the historical default).
+    #[default]
+    Alphabetical,
+    /// Suggestions matching the typed prefix first, then the rest, with ties
+    /// in each group broken by how often the suggestion has been run (see
+    /// `NuCompleter::record_command_usage`) and finally alphabetically.
+    Smart,
+}
+
+impl FromStr for CompletionSort {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "alphabetical" => Ok(Self::Alphabetical),
+            "smart" => Ok(Self::Smart),
+            _ => Err("expected either 'alphabetical' or 'smart'"),
+        }
+    }
+}
+
+impl ReconstructVal for CompletionSort {
+    fn reconstruct_value(&self, span: Span) -> Value {
+        let str = match self {
+            CompletionSort::Alphabetical => "alphabetical",
+            CompletionSort::Smart => "smart",
         };
         Value::string(str, span)
     }
@@ -49,7 +124,33 @@ pub(super) fn reconstruct_external(config: &Config, span: Span) -> Value {
             "max_results" => Value::int(config.max_external_completion_results, span),
             "completer" => reconstruct_external_completer(config, span),
             "enable" => Value::bool(config.enable_external_completion, span),
+            "timeout" => Value::duration(config.completions_external_timeout, span),
         },
         span,
     )
 }
+
+pub(super) fn reconstruct_case_sensitive_paths(config: &Config, span: Span) -> Value {
+    match config.completions_case_sensitive_paths {
+        Some(val) => Value::bool(val, span),
+        None => Value::nothing(span),
+    }
+}
+
+pub(super) fn reconstruct_transform(config: &Config, span: Span) -> Value {
+    match config.completions_transform.as_ref() {
+        Some(closure) => Value::closure(closure.clone(), span),
+        None => Value::nothing(span),
+    }
+}
+
+pub(super) fn reconstruct_path_roots(config: &Config, span: Span) -> Value {
+    Value::record(
+        config
+            .completions_path_roots
+            .iter()
+            .map(|(command, path)| (command.clone(), Value::string(path.clone(), span)))
+            .collect(),
+        span,
+    )
+}
@@ -10,7 +10,7 @@ use crate::{record, ShellError, Span, Value};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-pub use self::completer::CompletionAlgorithm;
+pub use self::completer::{CompletionAlgorithm, CompletionSort, FuzzyAnchor};
 pub use self::helper::extract_value;
 pub use self::hooks::Hooks;
 pub use self::output::ErrorStyle;
@@ -62,12 +62,21 @@ pub struct Config {
     pub footer_mode: FooterMode,
     pub float_precision: i64,
     pub max_external_completion_results: i64,
+    /// How long an external completer closure is allowed to run before it's
+    /// cancelled and completion falls back to file completions, in
+    /// nanoseconds. `0` disables the timeout.
+    pub completions_external_timeout: i64,
     pub recursion_limit: i64,
     pub filesize_format: String,
     pub use_ansi_coloring: bool,
     pub quick_completions: bool,
     pub partial_completions: bool,
     pub completion_algorithm: CompletionAlgorithm,
+    pub completion_sort: CompletionSort,
+    /// Where a `completions.algorithm = "fuzzy"` match must anchor to,
+    /// e.g. `"start"` to require the first typed character match the
+    /// candidate's first character. Ignored for other algorithms.
+    pub completions_fuzzy_anchor: FuzzyAnchor,
     pub edit_mode: EditBindings,
     pub history: HistoryConfig,
     pub keybindings: Vec<ParsedKeybinding>,
@@ -101,6 +110,34 @@ pub struct Config {
     pub use_kitty_protocol: bool,
     pub highlight_resolved_externals: bool,
     pub use_ls_colors_completions: bool,
+    pub completions_hidden_files: bool,
+    pub completions_append_slash: bool,
+    /// Whether file completions should be filtered using the nearest
+    /// `.gitignore`/`.ignore` rules, e.g. hiding `target/` in a Rust repo.
+    pub completions_use_ignore_files: bool,
+    /// Whether file/path completions should group directory suggestions
+    /// ahead of file suggestions, each group keeping its own sort order.
+    pub completions_dirs_first: bool,
+    /// Case sensitivity used specifically for file path completion. `None`
+    /// (the default) means "follow `completions.case_sensitive`", letting a
+    /// case-insensitive filesystem (or a user who just wants path completion
+    /// to be forgiving) opt out of the general case-sensitive setting without
+    /// affecting non-path completions like variables or commands.
+    pub completions_case_sensitive_paths: Option<bool>,
+    /// Closure `{|suggestion| ...}` applied to each suggestion's value
+    /// before it's returned, e.g. to strip a common prefix or lowercase it.
+    /// If it returns something other than a string, the suggestion is left
+    /// unchanged.
+    pub completions_transform: Option<Closure>,
+    /// Maps a command name to a base directory that `NuCompleter` should
+    /// resolve that command's file/path completions relative to, instead of
+    /// the current working directory, e.g. a project data directory for a
+    /// custom command that always operates there.
+    pub completions_path_roots: HashMap<String, String>,
+    /// Whether a command's own completion suggestion (e.g. `chain` in `chain
+    /// <tab>`) has its first example's `example` string attached to its
+    /// description, as a hint at the argument pattern to type next.
+    pub completions_show_examples: bool,
     /// Configuration for plugins.
     ///
     /// Users can provide configuration for a plugin through this entry.  The entry name must
@@ -140,11 +177,22 @@ impl Default for Config {
             quick_completions: true,
             partial_completions: true,
             completion_algorithm: CompletionAlgorithm::default(),
+            completion_sort: CompletionSort::default(),
+            completions_fuzzy_anchor: FuzzyAnchor::default(),
             enable_external_completion: true,
             max_external_completion_results: 100,
+            completions_external_timeout: 2_000_000_000, // 2sec
             recursion_limit: 50,
             external_completer: None,
             use_ls_colors_completions: true,
+            completions_hidden_files: true,
+            completions_append_slash: true,
+            completions_use_ignore_files: false,
+            completions_dirs_first: false,
+            completions_case_sensitive_paths: None,
+            completions_transform: None,
+            completions_path_roots: HashMap::new(),
+            completions_show_examples: false,
 
             filesize_metric: false,
             filesize_format: "auto".into(),
@@ -337,6 +385,20 @@ impl Value {
                                             value,
                                             &mut errors);
                                     }
+                                    "sort" => {
+                                        process_string_enum(
+                                            &mut config.completion_sort,
+                                            &[key, key2],
+                                            value,
+                                            &mut errors);
+                                    }
+                                    "fuzzy_anchor" => {
+                                        process_string_enum(
+                                            &mut config.completions_fuzzy_anchor,
+                                            &[key, key2],
+                                            value,
+                                            &mut errors);
+                                    }
                                     "case_sensitive" => {
                                         process_bool_config(value, &mut errors, &mut config.case_sensitive_completions);
                                     }
@@ -368,6 +430,22 @@ impl Value {
                                                         "enable" => {
                                                             process_bool_config(value, &mut errors, &mut config.enable_external_completion);
                                                         }
+                                                        "timeout" => {
+                                                            match value {
+                                                                Value::Duration { val, .. } => {
+                                                                    if *val >= 0 {
+                                                                        config.completions_external_timeout = *val;
+                                                                    } else {
+                                                                        report_invalid_value("must not be negative", span, &mut errors);
+                                                                        *val = config.completions_external_timeout;
+                                                                    }
+                                                                }
+                                                                _ => {
+                                                                    report_invalid_value("should be a duration", span, &mut errors);
+                                                                    *value = Value::duration(config.completions_external_timeout, span);
+                                                                }
+                                                            }
+                                                        }
                                                         _ => {
                                                             report_invalid_key(&[key, key2, key3], span, &mut errors);
                                                             return false;
@@ -384,6 +462,72 @@ impl Value {
                                     "use_ls_colors" => {
                                         process_bool_config(value, &mut errors, &mut config.use_ls_colors_completions);
                                     }
+                                    "hidden_files" => {
+                                        process_bool_config(value, &mut errors, &mut config.completions_hidden_files);
+                                    }
+                                    "append_slash" => {
+                                        process_bool_config(value, &mut errors, &mut config.completions_append_slash);
+                                    }
+                                    "use_ignore_files" => {
+                                        process_bool_config(value, &mut errors, &mut config.completions_use_ignore_files);
+                                    }
+                                    "dirs_first" => {
+                                        process_bool_config(value, &mut errors, &mut config.completions_dirs_first);
+                                    }
+                                    "case_sensitive_paths" => {
+                                        match value {
+                                            Value::Bool { val, .. } => {
+                                                config.completions_case_sensitive_paths = Some(*val);
+                                            }
+                                            Value::Nothing { .. } => {
+                                                config.completions_case_sensitive_paths = None;
+                                            }
+                                            _ => {
+                                                report_invalid_value("should be a bool or null", span, &mut errors);
+                                                *value = reconstruct_case_sensitive_paths(&config, span);
+                                            }
+                                        }
+                                    }
+                                    "transform" => {
+                                        if let Ok(v) = value.as_closure() {
+                                            config.completions_transform = Some(v.clone())
+                                        } else {
+                                            match value {
+                                                Value::Nothing { .. } => {
+                                                    config.completions_transform = None;
+                                                }
+                                                _ => {
+                                                    report_invalid_value("should be a closure or null", span, &mut errors);
+                                                    *value = reconstruct_transform(&config, span);
+                                                }
+                                            }
+                                        }
+                                    }
+                                    "path_roots" => {
+                                        if let Value::Record { val, .. } = value {
+                                            let mut roots = HashMap::new();
+                                            let mut all_strings = true;
+                                            for (command, path) in val.iter() {
+                                                if let Ok(path) = path.as_str() {
+                                                    roots.insert(command.clone(), path.to_string());
+                                                } else {
+                                                    report_invalid_value("should be a string", path.span(), &mut errors);
+                                                    all_strings = false;
+                                                }
+                                            }
+                                            if all_strings {
+                                                config.completions_path_roots = roots;
+                                            } else {
+                                                *value = reconstruct_path_roots(&config, span);
+                                            }
+                                        } else {
+                                            report_invalid_value("should be a record", span, &mut errors);
+                                            *value = reconstruct_path_roots(&config, span);
+                                        }
+                                    }
+                                    "show_examples" => {
+                                        process_bool_config(value, &mut errors, &mut config.completions_show_examples);
+                                    }
                                     _ => {
                                         report_invalid_key(&[key, key2], span, &mut errors);
                                         return false;
@@ -399,9 +543,19 @@ impl Value {
                                     "quick" => Value::bool(config.quick_completions, span),
                                     "partial" => Value::bool(config.partial_completions, span),
                                     "algorithm" => config.completion_algorithm.reconstruct_value(span),
+                                    "sort" => config.completion_sort.reconstruct_value(span),
+                                    "fuzzy_anchor" => config.completions_fuzzy_anchor.reconstruct_value(span),
                                     "case_sensitive" => Value::bool(config.case_sensitive_completions, span),
                                     "external" => reconstruct_external(&config, span),
                                     "use_ls_colors" => Value::bool(config.use_ls_colors_completions, span),
+                                    "hidden_files" => Value::bool(config.completions_hidden_files, span),
+                                    "append_slash" => Value::bool(config.completions_append_slash, span),
+                                    "use_ignore_files" => Value::bool(config.completions_use_ignore_files, span),
+                                    "dirs_first" => Value::bool(config.completions_dirs_first, span),
+                                    "case_sensitive_paths" => reconstruct_case_sensitive_paths(&config, span),
+                                    "transform" => reconstruct_transform(&config, span),
+                                    "path_roots" => reconstruct_path_roots(&config, span),
+                                    "show_examples" => Value::bool(config.completions_show_examples, span),
                                 },
                                 span,
                             );
@@ -704,7 +704,7 @@ impl<'a> StateWorkingSet<'a> {
 
     pub fn find_commands_by_predicate(
         &self,
-        predicate: impl Fn(&[u8]) -> bool,
+        predicate: impl Fn(&[u8], &dyn Command) -> bool,
         ignore_deprecated: bool,
     ) -> Vec<(Vec<u8>, Option<String>, CommandType)> {
         let mut output = vec![];
@@ -714,16 +714,18 @@ impl<'a> StateWorkingSet<'a> {
                 let overlay_frame = scope_frame.get_overlay(*overlay_id);
 
                 for decl in &overlay_frame.decls {
-                    if overlay_frame.visibility.is_decl_id_visible(decl.1) && predicate(decl.0) {
+                    if overlay_frame.visibility.is_decl_id_visible(decl.1) {
                         let command = self.get_decl(*decl.1);
                         if ignore_deprecated && command.signature().category == Category::Removed {
                             continue;
                         }
-                        output.push((
-                            decl.0.clone(),
-                            Some(command.usage().to_string()),
-                            command.command_type(),
-                        ));
+                        if predicate(decl.0, command) {
+                            output.push((
+                                decl.0.clone(),
+                                Some(command.usage().to_string()),
+                                command.command_type(),
+                            ));
+                        }
                     }
                 }
             }
@@ -255,6 +255,16 @@ pub(crate) fn create_nu_constant(engine_state: &EngineState, span: Span) -> Valu
     Value::record(record, span)
 }
 
+/// Returns the top-level record keys of the `$nu` constant (e.g. `pid`,
+/// `home-path`, `os-info`, ...), derived from the same construction used to
+/// populate `$nu` itself so the two can never drift apart.
+pub fn nu_constant_keys(engine_state: &EngineState) -> Vec<String> {
+    match create_nu_constant(engine_state, Span::unknown()) {
+        Value::Record { val, .. } => val.columns().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
 pub fn get_vendor_autoload_dir(engine_state: &EngineState) -> Option<PathBuf> {
     // pseudo code
     // if env var NU_VENDOR_AUTOLOAD_DIR is set, in any platform, use it